@@ -55,14 +55,16 @@ pub(crate) fn add_turbo_fish(acc: &mut Assists, ctx: &AssistContext) -> Option<(
 
 #[cfg(test)]
 mod tests {
-    use crate::tests::{check_assist, check_assist_not_applicable};
+    use crate::tests::{
+        assist_dir_tests, check_assist_not_applicable, check_assist_parses, test_data_dir,
+    };
 
     use super::*;
     use test_utils::mark;
 
     #[test]
     fn add_turbo_fish_function() {
-        check_assist(
+        check_assist_parses(
             add_turbo_fish,
             r#"
 fn make<T>() -> T {}
@@ -81,7 +83,7 @@ fn main() {
 
     #[test]
     fn add_turbo_fish_method() {
-        check_assist(
+        check_assist_parses(
             add_turbo_fish,
             r#"
 struct S;
@@ -131,4 +133,9 @@ fn main() {
 "#,
         );
     }
+
+    #[test]
+    fn add_turbo_fish_corpus() {
+        assist_dir_tests(add_turbo_fish, &test_data_dir(), &["add_turbo_fish"]);
+    }
 }
@@ -1,10 +1,15 @@
 mod generated;
 
+use std::path::{Path, PathBuf};
+
 use hir::Semantics;
 use ra_db::{fixture::WithFixture, FileId, FileRange, SourceDatabaseExt};
 use ra_ide_db::RootDatabase;
-use ra_syntax::TextRange;
-use test_utils::{assert_eq_text, extract_offset, extract_range};
+use ra_syntax::{SourceFile, TextRange};
+use test_utils::{
+    assert_eq_text, assert_expected_output, assert_panics_with, collect_rust_files, extract_offset,
+    extract_range, project_dir, CURSOR_MARKER,
+};
 
 use crate::{handlers::Handler, Assist, AssistConfig, AssistContext, Assists};
 use stdx::trim_indent;
@@ -18,6 +23,55 @@ pub(crate) fn check_assist(assist: Handler, ra_fixture_before: &str, ra_fixture_
     check(assist, ra_fixture_before, ExpectedResult::After(&ra_fixture_after));
 }
 
+/// Like `check_assist`, but also asserts that the edited text, with snippet
+/// placeholders filled in, parses without errors.
+pub(crate) fn check_assist_parses(
+    assist: Handler,
+    ra_fixture_before: &str,
+    ra_fixture_after: &str,
+) {
+    check_assist(assist, ra_fixture_before, ra_fixture_after);
+    assert_assist_output_parses(&trim_indent(ra_fixture_after));
+}
+
+/// Asserts that `output` of an assist is valid Rust once snippet placeholders
+/// like `${0:_}` are replaced with their default text.
+pub(crate) fn assert_assist_output_parses(output: &str) {
+    let text = strip_snippet_placeholders(output);
+    let parse = SourceFile::parse(&text);
+    if !parse.errors().is_empty() {
+        panic!("assist output has syntax errors: {:?}\n{}", parse.errors(), text);
+    }
+}
+
+/// Replaces `${N:text}` snippet placeholders with `text` and drops `$N` tab
+/// stops.
+fn strip_snippet_placeholders(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('$') {
+        res.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        if rest.starts_with('{') {
+            match rest.find('}') {
+                Some(end) => {
+                    res.push_str(rest[1..end].splitn(2, ':').nth(1).unwrap_or_default());
+                    rest = &rest[end + 1..];
+                }
+                None => res.push('$'),
+            }
+        } else {
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or_else(|| rest.len());
+            if digits == 0 {
+                res.push('$');
+            }
+            rest = &rest[digits..];
+        }
+    }
+    res.push_str(rest);
+    res
+}
+
 // FIXME: instead of having a separate function here, maybe use
 // `extract_ranges` and mark the target as `<target> </target>` in the
 // fixuture?
@@ -29,6 +83,43 @@ pub(crate) fn check_assist_not_applicable(assist: Handler, ra_fixture: &str) {
     check(assist, ra_fixture, ExpectedResult::NotApplicable);
 }
 
+/// Runs `handler` on every `.rs` file with a `<|>` marker in `test_data_dir`
+/// subdirectories defined by `paths`, and compares the edited text (or
+/// `not applicable`) with the matching `.txt` file.
+pub(crate) fn assist_dir_tests(handler: Handler, test_data_dir: &Path, paths: &[&str]) {
+    for (path, input_code) in collect_rust_files(test_data_dir, paths) {
+        if !input_code.contains(CURSOR_MARKER) {
+            continue;
+        }
+        let actual = match apply_assist(handler, &input_code) {
+            Some(after) => after,
+            None => "not applicable\n".to_string(),
+        };
+        assert_expected_output(&path.with_extension("txt"), &input_code, &actual);
+    }
+}
+
+fn apply_assist(handler: Handler, before: &str) -> Option<String> {
+    let (db, file_id, range_or_offset) = RootDatabase::with_range_or_offset(before);
+    let frange = FileRange { file_id, range: range_or_offset.into() };
+
+    let sema = Semantics::new(&db);
+    let config = AssistConfig::default();
+    let ctx = AssistContext::new(sema, &config, frange);
+    let mut acc = Assists::new_resolved(&ctx);
+    handler(&mut acc, &ctx);
+    let mut source_change = acc.finish_resolved().pop()?.source_change;
+    let change = source_change.source_file_edits.pop().unwrap();
+
+    let mut actual = db.file_text(change.file_id).as_ref().to_owned();
+    change.edit.apply(&mut actual);
+    Some(actual)
+}
+
+pub(crate) fn test_data_dir() -> PathBuf {
+    project_dir().join("crates/ra_assists/test_data")
+}
+
 fn check_doc_test(assist_id: &str, before: &str, after: &str) {
     let after = trim_indent(after);
     let (db, file_id, selection) = RootDatabase::with_range_or_offset(&before);
@@ -134,3 +225,11 @@ fn assist_order_if_expr() {
     assert_eq!(assists.next().expect("expected assist").assist.label, "Extract into variable");
     assert_eq!(assists.next().expect("expected assist").assist.label, "Replace with match");
 }
+
+#[test]
+fn assist_output_with_syntax_errors_is_rejected() {
+    assert_assist_output_parses("fn main() { make::<${0:_}>(); $0 }");
+    assert_panics_with("assist output has syntax errors[..]", || {
+        assert_assist_output_parses("fn main() { make::<${0:_}>(; }")
+    });
+}
@@ -0,0 +1,4 @@
+fn make<T>() -> T {}
+fn main() {
+    make<|>();
+}
@@ -46,7 +46,8 @@
 //! Metadata allows specifying all settings and variables
 //! that are available in a real rust project:
 //! - crate names via `crate:cratename`
-//! - dependencies via `deps:dep1,dep2`
+//! - dependencies via `deps:dep1,dep2`; proc-macro crates are marked as
+//!   `deps:dep1(proc-macro)`
 //! - configuration settings via `cfg:dbg=false,opt_level=2`
 //! - environment variables via `env:PATH=/bin,RUST_LOG=debug`
 //!
@@ -157,7 +158,8 @@ fn with_files(
             let prev = crates.insert(krate.clone(), crate_id);
             assert!(prev.is_none());
             for dep in meta.deps {
-                crate_deps.push((krate.clone(), dep))
+                let is_proc_macro = meta.proc_macro_deps.contains(&dep);
+                crate_deps.push((krate.clone(), dep, is_proc_macro))
             }
         } else if meta.path == "/main.rs" || meta.path == "/lib.rs" {
             assert!(default_crate_root.is_none());
@@ -183,10 +185,15 @@ fn with_files(
             Default::default(),
         );
     } else {
-        for (from, to) in crate_deps {
+        for (from, to, is_proc_macro) in crate_deps {
             let from_id = crates[&from];
             let to_id = crates[&to];
-            crate_graph.add_dep(from_id, CrateName::new(&to).unwrap(), to_id).unwrap();
+            let name = CrateName::new(&to).unwrap();
+            if is_proc_macro {
+                crate_graph.add_proc_macro_dep(from_id, name, to_id).unwrap();
+            } else {
+                crate_graph.add_dep(from_id, name, to_id).unwrap();
+            }
         }
     }
 
@@ -200,6 +207,7 @@ struct FileMeta {
     path: String,
     krate: Option<String>,
     deps: Vec<String>,
+    proc_macro_deps: Vec<String>,
     cfg: CfgOptions,
     edition: Edition,
     env: Env,
@@ -215,6 +223,7 @@ impl From<Fixture> for FileMeta {
             path: f.path,
             krate: f.krate,
             deps: f.deps,
+            proc_macro_deps: f.proc_macro_deps,
             cfg,
             edition: f
                 .edition
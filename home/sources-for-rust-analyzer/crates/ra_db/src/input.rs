@@ -139,6 +139,9 @@ pub struct Env {
 pub struct Dependency {
     pub crate_id: CrateId,
     pub name: SmolStr,
+    /// Whether the dependency is a proc-macro crate, whose macros are loaded
+    /// from a compiled dylib rather than expanded from its sources.
+    pub is_proc_macro: bool,
 }
 
 impl CrateGraph {
@@ -174,11 +177,32 @@ impl CrateGraph {
         from: CrateId,
         name: CrateName,
         to: CrateId,
+    ) -> Result<(), CyclicDependenciesError> {
+        self.add_dep_impl(from, name, to, false)
+    }
+
+    /// Like `add_dep`, but marks the edge as a dependency on a proc-macro
+    /// crate.
+    pub fn add_proc_macro_dep(
+        &mut self,
+        from: CrateId,
+        name: CrateName,
+        to: CrateId,
+    ) -> Result<(), CyclicDependenciesError> {
+        self.add_dep_impl(from, name, to, true)
+    }
+
+    fn add_dep_impl(
+        &mut self,
+        from: CrateId,
+        name: CrateName,
+        to: CrateId,
+        is_proc_macro: bool,
     ) -> Result<(), CyclicDependenciesError> {
         if self.dfs_find(from, to, &mut FxHashSet::default()) {
             return Err(CyclicDependenciesError);
         }
-        self.arena.get_mut(&from).unwrap().add_dep(name.0, to);
+        self.arena.get_mut(&from).unwrap().add_dep(name.0, to, is_proc_macro);
         Ok(())
     }
 
@@ -247,8 +271,8 @@ impl CrateId {
 }
 
 impl CrateData {
-    fn add_dep(&mut self, name: SmolStr, crate_id: CrateId) {
-        self.dependencies.push(Dependency { name, crate_id })
+    fn add_dep(&mut self, name: SmolStr, crate_id: CrateId, is_proc_macro: bool) {
+        self.dependencies.push(Dependency { name, crate_id, is_proc_macro })
     }
 }
 
@@ -429,7 +453,11 @@ mod tests {
             .is_ok());
         assert_eq!(
             graph[crate1].dependencies,
-            vec![Dependency { crate_id: crate2, name: "crate_name_with_dashes".into() }]
+            vec![Dependency {
+                crate_id: crate2,
+                name: "crate_name_with_dashes".into(),
+                is_proc_macro: false,
+            }]
         );
     }
 }
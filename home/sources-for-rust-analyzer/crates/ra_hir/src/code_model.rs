@@ -10,6 +10,7 @@ use hir_def::{
     docs::Documentation,
     expr::{BindingAnnotation, Pat, PatId},
     import_map,
+    path::path,
     per_ns::PerNs,
     resolver::{HasResolver, Resolver},
     type_ref::{Mutability, TypeRef},
@@ -27,9 +28,11 @@ use hir_ty::{
     display::{HirDisplayError, HirFormatter},
     expr::ExprValidator,
     method_resolution,
+    primitive::{FloatBitness, FloatTy, IntBitness, IntTy},
+    traits::{Solution, SolutionVariables},
     unsafe_validation::UnsafeValidator,
-    ApplicationTy, Canonical, GenericPredicate, InEnvironment, Substs, TraitEnvironment, Ty,
-    TyDefId, TypeCtor,
+    ApplicationTy, BoundVar, Canonical, DebruijnIndex, GenericPredicate, InEnvironment, Obligation,
+    ProjectionPredicate, ProjectionTy, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
 };
 use ra_db::{CrateId, CrateName, Edition, FileId};
 use ra_prof::profile;
@@ -645,6 +648,16 @@ impl DefWithBody {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// `&self`
+    Shared,
+    /// `&mut self`
+    Exclusive,
+    /// `self`, or `self` with some other explicit type
+    Owned,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Function {
     pub(crate) id: FunctionId,
@@ -671,6 +684,29 @@ impl Function {
         db.function_data(self.id).params.clone()
     }
 
+    /// How the function takes its `self` parameter, if it has one.
+    pub fn self_param_access(self, db: &dyn HirDatabase) -> Option<Access> {
+        let data = db.function_data(self.id);
+        if !data.has_self_param {
+            return None;
+        }
+        let access = match data.params.first()? {
+            TypeRef::Reference(_, Mutability::Shared) => Access::Shared,
+            TypeRef::Reference(_, Mutability::Mut) => Access::Exclusive,
+            _ => Access::Owned,
+        };
+        Some(access)
+    }
+
+    /// The declared return type, in terms of the function's own generic
+    /// parameters.
+    pub fn ret_type(self, db: &dyn HirDatabase) -> Type {
+        let substs = Substs::type_params(db, self.id);
+        let sig = db.callable_item_signature(self.id.into()).subst(&substs);
+        let krate = self.id.lookup(db.upcast()).module(db.upcast()).krate;
+        Type::new(db, krate, self.id, sig.ret().clone())
+    }
+
     pub fn is_unsafe(self, db: &dyn HirDatabase) -> bool {
         db.function_data(self.id).is_unsafe
     }
@@ -1161,6 +1197,17 @@ impl Type {
         matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }))
     }
 
+    pub fn is_unit(&self) -> bool {
+        matches!(
+            self.ty.value,
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { cardinality: 0 }, .. })
+        )
+    }
+
+    pub fn is_str(&self) -> bool {
+        matches!(self.ty.value, Ty::Apply(ApplicationTy { ctor: TypeCtor::Str, .. }))
+    }
+
     pub fn is_mutable_reference(&self) -> bool {
         matches!(
             self.ty.value,
@@ -1172,6 +1219,51 @@ impl Type {
         matches!(self.ty.value, Ty::Unknown)
     }
 
+    /// The output of built-in indexing of slices, arrays and `str`, without
+    /// going through the `Index` trait.
+    pub fn builtin_index_output(&self) -> Option<Type> {
+        method_resolution::builtin_index_output(&self.ty.value).map(|ty| self.derived(ty))
+    }
+
+    /// Whether this is `i32` or `f64`, the types integer and float literals
+    /// fall back to when nothing else constrains them.
+    pub fn is_literal_fallback(&self) -> bool {
+        match &self.ty.value {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Int(it), .. }) => *it == IntTy::i32(),
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Float(it), .. }) => *it == FloatTy::f64(),
+            _ => false,
+        }
+    }
+
+    /// Returns the size of this type in bytes, if it doesn't depend on the
+    /// compilation target or on other definitions (i.e. for fixed-size
+    /// scalars and `()`).
+    pub fn size_of(&self) -> Option<u64> {
+        let a_ty = match &self.ty.value {
+            Ty::Apply(a_ty) => a_ty,
+            _ => return None,
+        };
+        let size = match a_ty.ctor {
+            TypeCtor::Bool => 1,
+            TypeCtor::Char => 4,
+            TypeCtor::Int(int_ty) => match int_ty.bitness {
+                IntBitness::X8 => 1,
+                IntBitness::X16 => 2,
+                IntBitness::X32 => 4,
+                IntBitness::X64 => 8,
+                IntBitness::X128 => 16,
+                IntBitness::Xsize => return None,
+            },
+            TypeCtor::Float(float_ty) => match float_ty.bitness {
+                FloatBitness::X32 => 4,
+                FloatBitness::X64 => 8,
+            },
+            TypeCtor::Tuple { cardinality: 0 } => 0,
+            _ => return None,
+        };
+        Some(size)
+    }
+
     /// Checks that particular type `ty` implements `std::future::Future`.
     /// This function is used in `.await` syntax completion.
     pub fn impls_future(&self, db: &dyn HirDatabase) -> bool {
@@ -1194,6 +1286,28 @@ impl Type {
         )
     }
 
+    /// Checks whether this type implements the `Copy` lang item.
+    pub fn is_copy(&self, db: &dyn HirDatabase) -> bool {
+        self.try_is_copy(db).unwrap_or(false)
+    }
+
+    /// Like `is_copy`, but returns `None` if the crate has no `Copy` lang
+    /// item to check against.
+    pub fn try_is_copy(&self, db: &dyn HirDatabase) -> Option<bool> {
+        let krate = self.krate;
+
+        let copy_trait = db.lang_item(krate, "copy".into()).and_then(|it| it.as_trait())?;
+
+        let canonical_ty = Canonical { value: self.ty.value.clone(), num_vars: 0 };
+        Some(method_resolution::implements_trait(
+            &canonical_ty,
+            db,
+            self.ty.environment.clone(),
+            krate,
+            copy_trait,
+        ))
+    }
+
     pub fn impls_trait(&self, db: &dyn HirDatabase, trait_: Trait, args: &[Type]) -> bool {
         let trait_ref = hir_ty::TraitRef {
             trait_: trait_.id,
@@ -1214,6 +1328,63 @@ impl Type {
         db.trait_solve(self.krate, goal).is_some()
     }
 
+    /// Returns the error type `<Self as Try>::Error`. For the return type of
+    /// a function, this is what `?` converts errors to via `From`.
+    pub fn try_error_type(&self, db: &dyn HirDatabase) -> Option<Type> {
+        let krate = self.krate;
+        let root = ModuleId { krate, local_id: db.crate_def_map(krate).root };
+        let try_trait =
+            root.resolver(db.upcast()).resolve_known_trait(db.upcast(), &path![core::ops::Try])?;
+        let error_alias = db.trait_data(try_trait).associated_type_by_name(&name![Error])?;
+        self.normalize_trait_assoc_type(db, try_trait, error_alias)
+    }
+
+    fn normalize_trait_assoc_type(
+        &self,
+        db: &dyn HirDatabase,
+        trait_: TraitId,
+        alias: TypeAliasId,
+    ) -> Option<Type> {
+        let parameters = Substs::build_for_def(db, trait_)
+            .push(self.ty.value.clone())
+            .fill_with_unknown()
+            .build();
+        let projection_ty = ProjectionTy { associated_ty: alias, parameters };
+        self.normalize_projection(db, &projection_ty).map(|ty| self.derived(ty))
+    }
+
+    /// Returns a function normalizing projection types in the environment of
+    /// this type, for `HirDisplayWrapper::with_projection_normalizer`.
+    pub fn projection_normalizer<'a>(
+        &'a self,
+        db: &'a dyn HirDatabase,
+    ) -> impl Fn(&ProjectionTy) -> Option<Ty> + 'a {
+        move |projection_ty| self.normalize_projection(db, projection_ty)
+    }
+
+    fn normalize_projection(
+        &self,
+        db: &dyn HirDatabase,
+        projection_ty: &ProjectionTy,
+    ) -> Option<Ty> {
+        let predicate = ProjectionPredicate {
+            projection_ty: projection_ty.clone(),
+            ty: Ty::Bound(BoundVar::new(DebruijnIndex::INNERMOST, 0)),
+        };
+        let goal = Canonical {
+            value: InEnvironment::new(
+                self.ty.environment.clone(),
+                Obligation::Projection(predicate),
+            ),
+            num_vars: 1,
+        };
+
+        match db.trait_solve(self.krate, goal)? {
+            Solution::Unique(SolutionVariables(subst)) => subst.value.first().cloned(),
+            Solution::Ambig(_) => None,
+        }
+    }
+
     // FIXME: this method is broken, as it doesn't take closures into account.
     pub fn as_callable(&self) -> Option<CallableDef> {
         Some(self.ty.value.as_callable()?.0)
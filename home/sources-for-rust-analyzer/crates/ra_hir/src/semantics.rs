@@ -224,10 +224,27 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.analyze(expr.syntax()).type_of(self.db, &expr)
     }
 
+    /// The type of an index expression into a slice, array or `str`, read off
+    /// the type of its base without going through the `Index` trait.
+    pub fn type_of_builtin_index(&self, expr: &ast::IndexExpr) -> Option<Type> {
+        let output = self.type_of_expr(&expr.base()?)?.builtin_index_output()?;
+        // Slices and arrays indexed by a range give a slice, not an element,
+        // and `str` can only be indexed by ranges.
+        let is_range = matches!(expr.index()?, ast::Expr::RangeExpr(_));
+        if is_range != output.is_str() {
+            return None;
+        }
+        Some(output)
+    }
+
     pub fn type_of_pat(&self, pat: &ast::Pat) -> Option<Type> {
         self.analyze(pat.syntax()).type_of_pat(self.db, &pat)
     }
 
+    pub fn type_of_self(&self, param: &ast::SelfParam) -> Option<Type> {
+        self.analyze(param.syntax()).type_of_self(self.db, &param)
+    }
+
     pub fn resolve_method_call(&self, call: &ast::MethodCallExpr) -> Option<Function> {
         self.analyze(call.syntax()).resolve_method_call(self.db, call)
     }
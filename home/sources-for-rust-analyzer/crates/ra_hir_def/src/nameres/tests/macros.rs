@@ -660,3 +660,25 @@ fn expand_multiple_derive() {
     );
     assert_eq!(map.modules[map.root].scope.impls().len(), 2);
 }
+
+#[test]
+fn fixture_marks_proc_macro_deps() {
+    let db = TestDB::with_files(
+        r"
+        //- /main.rs crate:main deps:foo,bar(proc-macro)
+        //- /foo.rs crate:foo
+        //- /bar.rs crate:bar
+        ",
+    );
+    let crate_graph = db.crate_graph();
+    let main = crate_graph
+        .iter()
+        .find(|&krate| crate_graph[krate].display_name.as_ref().unwrap().to_string() == "main")
+        .unwrap();
+    let deps = crate_graph[main]
+        .dependencies
+        .iter()
+        .map(|dep| (dep.name.to_string(), dep.is_proc_macro))
+        .collect::<Vec<_>>();
+    assert_eq!(deps, vec![("foo".to_string(), false), ("bar".to_string(), true)]);
+}
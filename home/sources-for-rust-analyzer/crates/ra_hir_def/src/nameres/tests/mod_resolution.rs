@@ -837,3 +837,33 @@ fn nested_out_of_line_module_with_path() {
         ⋮X: t v
     "###);
 }
+
+#[test]
+fn module_resolution_path_attribute_to_custom_location() {
+    let map = def_map(
+        r###"
+        //- /lib.rs
+        #[path = "custom/location.rs"]
+        mod m;
+        mod user;
+
+        //- /user.rs
+        use crate::m::Custom;
+
+        //- /custom/location.rs
+        pub struct Custom;
+        "###,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮m: t
+        ⋮user: t
+        ⋮
+        ⋮crate::m
+        ⋮Custom: t v
+        ⋮
+        ⋮crate::user
+        ⋮Custom: t v
+    "###);
+}
@@ -168,6 +168,7 @@ pub mod known {
         Item,
         Try,
         Ok,
+        Error,
         Future,
         Result,
         Output,
@@ -182,6 +183,34 @@ pub mod known {
         Neg,
         Not,
         Index,
+        // Components of known path (operator method name)
+        add,
+        sub,
+        mul,
+        div,
+        rem,
+        shl,
+        shr,
+        bitxor,
+        bitor,
+        bitand,
+        add_assign,
+        sub_assign,
+        mul_assign,
+        div_assign,
+        rem_assign,
+        shl_assign,
+        shr_assign,
+        bitxor_assign,
+        bitor_assign,
+        bitand_assign,
+        eq,
+        ne,
+        lt,
+        le,
+        gt,
+        ge,
+        index,
         // Builtin macros
         file,
         column,
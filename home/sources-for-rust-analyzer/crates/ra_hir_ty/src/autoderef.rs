@@ -17,7 +17,7 @@ use crate::{
     BoundVar, Canonical, DebruijnIndex, Obligation, Substs, TraitRef, Ty,
 };
 
-const AUTODEREF_RECURSION_LIMIT: usize = 10;
+pub(crate) const AUTODEREF_RECURSION_LIMIT: usize = 10;
 
 pub fn autoderef<'a>(
     db: &'a dyn HirDatabase,
@@ -43,7 +43,7 @@ pub(crate) fn deref(
     }
 }
 
-fn deref_by_trait(
+pub(crate) fn deref_by_trait(
     db: &dyn HirDatabase,
     krate: CrateId,
     ty: InEnvironment<&Canonical<Ty>>,
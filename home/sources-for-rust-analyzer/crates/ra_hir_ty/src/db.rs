@@ -3,12 +3,13 @@
 use std::sync::Arc;
 
 use hir_def::{
-    db::DefDatabase, DefWithBodyId, FunctionId, GenericDefId, ImplId, LocalFieldId, TypeParamId,
-    VariantId,
+    db::DefDatabase, AssocItemId, DefWithBodyId, FunctionId, GenericDefId, ImplId, LocalFieldId,
+    TypeParamId, VariantId,
 };
 use ra_arena::map::ArenaMap;
 use ra_db::{impl_intern_key, salsa, CrateId, Upcast};
 use ra_prof::profile;
+use rustc_hash::FxHashMap;
 
 use crate::{
     method_resolution::CrateImplDefs,
@@ -73,6 +74,9 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
     #[salsa::invoke(crate::method_resolution::CrateImplDefs::impls_from_deps_query)]
     fn impls_from_deps(&self, krate: CrateId) -> Arc<CrateImplDefs>;
 
+    #[salsa::invoke(crate::method_resolution::impl_items_by_name_query)]
+    fn impl_items_by_name(&self, impl_id: ImplId) -> Arc<FxHashMap<Name, Vec<AssocItemId>>>;
+
     // Interned IDs for Chalk integration
     #[salsa::interned]
     fn intern_type_ctor(&self, type_ctor: TypeCtor) -> crate::TypeCtorId;
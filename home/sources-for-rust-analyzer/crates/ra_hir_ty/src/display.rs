@@ -7,8 +7,8 @@ use crate::{
     Obligation, OpaqueTyId, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
 };
 use hir_def::{
-    find_path, generics::TypeParamProvenance, item_scope::ItemInNs, AdtId, AssocContainerId,
-    Lookup, ModuleId,
+    find_path, generics::TypeParamProvenance, item_scope::ItemInNs, type_ref::Mutability, AdtId,
+    AssocContainerId, Lookup, ModuleId,
 };
 use hir_expand::name::Name;
 
@@ -19,9 +19,17 @@ pub struct HirFormatter<'a> {
     curr_size: usize,
     pub(crate) max_size: Option<usize>,
     omit_verbose_types: bool,
+    mutability_style: MutabilityStyle,
+    max_depth: Option<usize>,
+    depth: usize,
+    type_arg_separator: &'a str,
+    projection_normalizer: Option<&'a ProjectionNormalizer<'a>>,
     display_target: DisplayTarget,
 }
 
+/// Resolves a projection type to its normalized form, if it can.
+pub type ProjectionNormalizer<'a> = dyn Fn(&ProjectionTy) -> Option<Ty> + 'a;
+
 pub trait HirDisplay {
     fn hir_fmt(&self, f: &mut HirFormatter) -> Result<(), HirDisplayError>;
 
@@ -36,6 +44,10 @@ pub trait HirDisplay {
             t: self,
             max_size: None,
             omit_verbose_types: false,
+            mutability_style: MutabilityStyle::Standard,
+            max_depth: None,
+            type_arg_separator: DEFAULT_TYPE_ARG_SEPARATOR,
+            projection_normalizer: None,
             display_target: DisplayTarget::Diagnostics,
         }
     }
@@ -55,6 +67,10 @@ pub trait HirDisplay {
             t: self,
             max_size,
             omit_verbose_types: true,
+            mutability_style: MutabilityStyle::Standard,
+            max_depth: None,
+            type_arg_separator: DEFAULT_TYPE_ARG_SEPARATOR,
+            projection_normalizer: None,
             display_target: DisplayTarget::Diagnostics,
         }
     }
@@ -74,6 +90,11 @@ pub trait HirDisplay {
             curr_size: 0,
             max_size: None,
             omit_verbose_types: false,
+            mutability_style: MutabilityStyle::Standard,
+            max_depth: None,
+            depth: 0,
+            type_arg_separator: DEFAULT_TYPE_ARG_SEPARATOR,
+            projection_normalizer: None,
             display_target: DisplayTarget::SourceCode { module_id },
         }) {
             Ok(()) => {}
@@ -101,6 +122,15 @@ impl<'a> HirFormatter<'a> {
         Ok(())
     }
 
+    /// Writes a list of generic arguments, without the angle brackets.
+    fn write_type_args<T: HirDisplay>(
+        &mut self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> Result<(), HirDisplayError> {
+        let sep = self.type_arg_separator;
+        self.write_joined(iter, sep)
+    }
+
     /// This allows using the `write!` macro directly with a `HirFormatter`.
     pub fn write_fmt(&mut self, args: fmt::Arguments) -> Result<(), HirDisplayError> {
         // We write to a buffer first to track output size
@@ -123,6 +153,45 @@ impl<'a> HirFormatter<'a> {
     pub fn omit_verbose_types(&self) -> bool {
         self.omit_verbose_types
     }
+
+    fn exceeds_max_depth(&self) -> bool {
+        self.max_depth.map_or(false, |max_depth| self.depth > max_depth)
+    }
+
+    /// Writes a type that is part of the current one, like the pointee of a
+    /// reference, with all of this formatter's settings. The nested type
+    /// shares the depth of the type currently being formatted.
+    fn write_nested(&mut self, t: &Ty) -> Result<(), HirDisplayError> {
+        let depth = self.depth;
+        self.depth = depth.saturating_sub(1);
+        let res = t.hir_fmt(self);
+        self.depth = depth;
+        res
+    }
+}
+
+/// How the mutability of reference types is spelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutabilityStyle {
+    /// `&mut T`
+    Standard,
+    /// `&m T`
+    Compact,
+}
+
+impl Default for MutabilityStyle {
+    fn default() -> Self {
+        MutabilityStyle::Standard
+    }
+}
+
+impl MutabilityStyle {
+    fn ref_keyword(self, mutability: Mutability) -> &'static str {
+        match (self, mutability) {
+            (MutabilityStyle::Compact, Mutability::Mut) => "m ",
+            _ => mutability.as_keyword_for_ref(),
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -164,9 +233,44 @@ pub struct HirDisplayWrapper<'a, T> {
     t: &'a T,
     max_size: Option<usize>,
     omit_verbose_types: bool,
+    mutability_style: MutabilityStyle,
+    max_depth: Option<usize>,
+    type_arg_separator: &'a str,
+    projection_normalizer: Option<&'a ProjectionNormalizer<'a>>,
     display_target: DisplayTarget,
 }
 
+impl<'a, T> HirDisplayWrapper<'a, T> {
+    pub fn with_mutability_style(mut self, mutability_style: MutabilityStyle) -> Self {
+        self.mutability_style = mutability_style;
+        self
+    }
+
+    /// Replaces types nested deeper than `max_depth` generic arguments with
+    /// `…` while formatting, so deeply nested types are cheap to display.
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Separates the arguments of generic argument lists like `Foo<A, B>`;
+    /// `", "` by default.
+    pub fn with_type_arg_separator(mut self, type_arg_separator: &'a str) -> Self {
+        self.type_arg_separator = type_arg_separator;
+        self
+    }
+
+    /// Shows projection types like `<S as Trait>::Assoc` as whatever
+    /// `projection_normalizer` resolves them to, where it does.
+    pub fn with_projection_normalizer(
+        mut self,
+        projection_normalizer: Option<&'a ProjectionNormalizer<'a>>,
+    ) -> Self {
+        self.projection_normalizer = projection_normalizer;
+        self
+    }
+}
+
 impl<'a, T> fmt::Display for HirDisplayWrapper<'a, T>
 where
     T: HirDisplay,
@@ -179,6 +283,11 @@ where
             curr_size: 0,
             max_size: self.max_size,
             omit_verbose_types: self.omit_verbose_types,
+            mutability_style: self.mutability_style,
+            max_depth: self.max_depth,
+            depth: 0,
+            type_arg_separator: self.type_arg_separator,
+            projection_normalizer: self.projection_normalizer,
             display_target: self.display_target,
         }) {
             Ok(()) => Ok(()),
@@ -192,6 +301,7 @@ where
 }
 
 const TYPE_HINT_TRUNCATION: &str = "…";
+const DEFAULT_TYPE_ARG_SEPARATOR: &str = ", ";
 
 impl HirDisplay for &Ty {
     fn hir_fmt(&self, f: &mut HirFormatter) -> Result<(), HirDisplayError> {
@@ -212,25 +322,23 @@ impl HirDisplay for ApplicationTy {
             TypeCtor::Float(t) => write!(f, "{}", t)?,
             TypeCtor::Str => write!(f, "str")?,
             TypeCtor::Slice => {
-                let t = self.parameters.as_single();
-                write!(f, "[{}]", t.display(f.db))?;
+                write!(f, "[")?;
+                f.write_nested(self.parameters.as_single())?;
+                write!(f, "]")?;
             }
             TypeCtor::Array => {
-                let t = self.parameters.as_single();
-                write!(f, "[{}; _]", t.display(f.db))?;
+                write!(f, "[")?;
+                f.write_nested(self.parameters.as_single())?;
+                write!(f, "; _]")?;
             }
             TypeCtor::RawPtr(m) => {
-                let t = self.parameters.as_single();
-                write!(f, "*{}{}", m.as_keyword_for_ptr(), t.display(f.db))?;
+                write!(f, "*{}", m.as_keyword_for_ptr())?;
+                f.write_nested(self.parameters.as_single())?;
             }
             TypeCtor::Ref(m) => {
-                let t = self.parameters.as_single();
-                let ty_display = if f.omit_verbose_types() {
-                    t.display_truncated(f.db, f.max_size)
-                } else {
-                    t.display(f.db)
-                };
-                write!(f, "&{}{}", m.as_keyword_for_ref(), ty_display)?;
+                let mutability = f.mutability_style.ref_keyword(m);
+                write!(f, "&{}", mutability)?;
+                f.write_nested(self.parameters.as_single())?;
             }
             TypeCtor::Never => write!(f, "!")?,
             TypeCtor::Tuple { .. } => {
@@ -270,7 +378,7 @@ impl HirDisplay for ApplicationTy {
                     // We print all params except implicit impl Trait params. Still a bit weird; should we leave out parent and self?
                     if total_len > 0 {
                         write!(f, "<")?;
-                        f.write_joined(&self.parameters.0[..total_len], ", ")?;
+                        f.write_type_args(&self.parameters.0[..total_len])?;
                         write!(f, ">")?;
                     }
                 }
@@ -340,7 +448,7 @@ impl HirDisplay for ApplicationTy {
                         };
                     if !parameters_to_write.is_empty() {
                         write!(f, "<")?;
-                        f.write_joined(parameters_to_write, ", ")?;
+                        f.write_type_args(parameters_to_write)?;
                         write!(f, ">")?;
                     }
                 }
@@ -355,7 +463,7 @@ impl HirDisplay for ApplicationTy {
                 write!(f, "{}::{}", trait_.name, type_alias.name)?;
                 if self.parameters.len() > 0 {
                     write!(f, "<")?;
-                    f.write_joined(&*self.parameters.0, ", ")?;
+                    f.write_type_args(&*self.parameters.0)?;
                     write!(f, ">")?;
                 }
             }
@@ -406,7 +514,7 @@ impl HirDisplay for ProjectionTy {
         write!(f, "<{} as {}", self.parameters[0].display(f.db), trait_.name)?;
         if self.parameters.len() > 1 {
             write!(f, "<")?;
-            f.write_joined(&self.parameters[1..], ", ")?;
+            f.write_type_args(&self.parameters[1..])?;
             write!(f, ">")?;
         }
         write!(f, ">::{}", f.db.type_alias_data(self.associated_ty).name)?;
@@ -416,13 +524,17 @@ impl HirDisplay for ProjectionTy {
 
 impl HirDisplay for Ty {
     fn hir_fmt(&self, f: &mut HirFormatter) -> Result<(), HirDisplayError> {
-        if f.should_truncate() {
+        if f.should_truncate() || f.exceeds_max_depth() {
             return write!(f, "{}", TYPE_HINT_TRUNCATION);
         }
+        f.depth += 1;
 
         match self {
             Ty::Apply(a_ty) => a_ty.hir_fmt(f)?,
-            Ty::Projection(p_ty) => p_ty.hir_fmt(f)?,
+            Ty::Projection(p_ty) => match f.projection_normalizer.and_then(|it| it(p_ty)) {
+                Some(ty) => ty.hir_fmt(f)?,
+                None => p_ty.hir_fmt(f)?,
+            },
             Ty::Placeholder(id) => {
                 let generics = generics(f.db.upcast(), id.parent);
                 let param_data = &generics.params.types[id.local_id];
@@ -463,6 +575,7 @@ impl HirDisplay for Ty {
             Ty::Unknown => write!(f, "{{unknown}}")?,
             Ty::Infer(..) => write!(f, "_")?,
         }
+        f.depth -= 1;
         Ok(())
     }
 }
@@ -494,7 +607,7 @@ fn write_bounds_like_dyn_trait(
                 write!(f, "{}", f.db.trait_data(trait_ref.trait_).name)?;
                 if trait_ref.substs.len() > 1 {
                     write!(f, "<")?;
-                    f.write_joined(&trait_ref.substs[1..], ", ")?;
+                    f.write_type_args(&trait_ref.substs[1..])?;
                     // there might be assoc type bindings, so we leave the angle brackets open
                     angle_open = true;
                 }
@@ -546,7 +659,7 @@ impl TraitRef {
         write!(f, "{}", f.db.trait_data(self.trait_).name)?;
         if self.substs.len() > 1 {
             write!(f, "<")?;
-            f.write_joined(&self.substs[1..], ", ")?;
+            f.write_type_args(&self.substs[1..])?;
             write!(f, ">")?;
         }
         Ok(())
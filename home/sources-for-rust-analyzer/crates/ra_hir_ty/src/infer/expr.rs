@@ -674,7 +674,7 @@ impl<'a> InferenceContext<'a> {
     ) -> Ty {
         for stmt in statements {
             match stmt {
-                Statement::Let { pat, type_ref, initializer } => {
+                Statement::Let { pat, type_ref, initializer, else_branch } => {
                     let decl_ty =
                         type_ref.as_ref().map(|tr| self.make_ty(tr)).unwrap_or(Ty::Unknown);
 
@@ -691,6 +691,10 @@ impl<'a> InferenceContext<'a> {
 
                     let ty = self.resolve_ty_as_possible(ty);
                     self.infer_pat(*pat, &ty, BindingMode::default());
+
+                    if let Some(expr) = else_branch {
+                        self.infer_expr(*expr, &Expectation::has_type(Ty::simple(TypeCtor::Never)));
+                    }
                 }
                 Statement::Expr(expr) => {
                     self.infer_expr(*expr, &Expectation::none());
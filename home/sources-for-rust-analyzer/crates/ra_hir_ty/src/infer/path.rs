@@ -219,6 +219,10 @@ impl<'a> InferenceContext<'a> {
         let canonical_ty = self.canonicalizer().canonicalize_ty(ty.clone());
         let krate = self.resolver.krate()?;
         let traits_in_scope = self.resolver.traits_in_scope(self.db.upcast());
+        let mode = match id {
+            ExprOrPatId::ExprId(_) => method_resolution::LookupMode::Path,
+            ExprOrPatId::PatId(_) => method_resolution::LookupMode::Pattern,
+        };
 
         method_resolution::iterate_method_candidates(
             &canonical_ty.value,
@@ -227,7 +231,7 @@ impl<'a> InferenceContext<'a> {
             krate,
             &traits_in_scope,
             Some(name),
-            method_resolution::LookupMode::Path,
+            mode,
             move |_ty, item| {
                 let (def, container) = match item {
                     AssocItemId::FunctionId(f) => {
@@ -1067,7 +1067,10 @@ fn type_for_adt(db: &dyn HirDatabase, adt: AdtId) -> Binders<Ty> {
     Binders::new(substs.len(), Ty::apply(TypeCtor::Adt(adt), substs))
 }
 
+/// Type aliases are expanded right here, during lowering, so that method
+/// resolution and friends only ever see the aliased type.
 fn type_for_type_alias(db: &dyn HirDatabase, t: TypeAliasId) -> Binders<Ty> {
+    mark::hit!(type_alias_expanded);
     let generics = generics(db.upcast(), t.into());
     let resolver = t.resolver(db.upcast());
     let ctx =
@@ -2,14 +2,21 @@
 //! For details about how this works in rustc, see the method lookup page in the
 //! [rustc guide](https://rust-lang.github.io/rustc-guide/method-lookup.html)
 //! and the corresponding code mostly in librustc_typeck/check/method/probe.rs.
-use std::sync::Arc;
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use arrayvec::ArrayVec;
 use hir_def::{
-    lang_item::LangItemTarget, type_ref::Mutability, AssocContainerId, AssocItemId, FunctionId,
-    HasModule, ImplId, Lookup, TraitId,
+    expr::BinaryOp, lang_item::LangItemTarget, type_ref::Mutability, AssocContainerId, AssocItemId,
+    FunctionId, HasModule, ImplId, Lookup, TraitId,
 };
-use hir_expand::name::Name;
+use hir_expand::name::{name, Name};
 use ra_db::CrateId;
 use ra_prof::profile;
 use rustc_hash::{FxHashMap, FxHashSet};
@@ -17,7 +24,8 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use super::Substs;
 use crate::{
     autoderef, db::HirDatabase, primitive::FloatBitness, utils::all_super_traits, ApplicationTy,
-    Canonical, DebruijnIndex, InEnvironment, TraitEnvironment, TraitRef, Ty, TypeCtor, TypeWalk,
+    BoundVar, Canonical, DebruijnIndex, InEnvironment, TraitEnvironment, TraitRef, Ty, TypeCtor,
+    TypeWalk,
 };
 
 /// This is used as a key for indexing impls.
@@ -28,8 +36,10 @@ pub enum TyFingerprint {
 
 impl TyFingerprint {
     /// Creates a TyFingerprint for looking up an impl. Only certain types can
-    /// have impls: if we have some `struct S`, we can have an `impl S`, but not
-    /// `impl &S`. Hence, this will return `None` for reference types and such.
+    /// have impls: if we have some `struct S`, we can have an `impl S`, and a
+    /// trait can be implemented for `&S`, but not for a type variable. Hence,
+    /// this will return `None` for types that aren't applications of a type
+    /// constructor.
     pub(crate) fn for_impl(ty: &Ty) -> Option<TyFingerprint> {
         match ty {
             Ty::Apply(a_ty) => Some(TyFingerprint::Apply(a_ty.ctor)),
@@ -38,6 +48,21 @@ impl TyFingerprint {
     }
 }
 
+impl fmt::Display for TyFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TyFingerprint::Apply(ctor) => write!(f, "Apply({:?})", ctor),
+        }
+    }
+}
+
+/// Returns the fingerprint under which impls for `ty` are looked up during
+/// method resolution, or `None` if `ty` isn't an application of a type
+/// constructor (e.g. because it's a type parameter).
+pub fn fingerprint_for_method_lookup(ty: &Ty) -> Option<TyFingerprint> {
+    TyFingerprint::for_impl(ty)
+}
+
 /// A queryable and mergeable collection of impls.
 #[derive(Debug, PartialEq, Eq)]
 pub struct CrateImplDefs {
@@ -76,7 +101,9 @@ impl CrateImplDefs {
         // `impls_in_crate`.
         // As we might visit crates multiple times, `merge` has to deduplicate impls to avoid
         // wasting memory.
-        for dep in &crate_graph[krate].dependencies {
+        // Proc-macro crates only export their macros, and their own dependencies are built for
+        // the host, so none of their impls can apply to types `krate` can name.
+        for dep in crate_graph[krate].dependencies.iter().filter(|dep| !dep.is_proc_macro) {
             res.merge(&db.impls_from_deps(dep.crate_id));
             res.merge(&db.impls_in_crate(dep.crate_id));
         }
@@ -84,6 +111,25 @@ impl CrateImplDefs {
         Arc::new(res)
     }
 
+    /// Assembles a `CrateImplDefs` from already fingerprinted impls, without
+    /// consulting the database.
+    pub fn from_raw(
+        inherent_impls: impl IntoIterator<Item = (TyFingerprint, ImplId)>,
+        trait_impls: impl IntoIterator<Item = (TraitId, Option<TyFingerprint>, ImplId)>,
+    ) -> CrateImplDefs {
+        let mut res = CrateImplDefs {
+            inherent_impls: FxHashMap::default(),
+            impls_by_trait: FxHashMap::default(),
+        };
+        for (fp, impl_id) in inherent_impls {
+            res.inherent_impls.entry(fp).or_default().push(impl_id);
+        }
+        for (trait_, fp, impl_id) in trait_impls {
+            res.impls_by_trait.entry(trait_).or_default().entry(fp).or_default().push(impl_id);
+        }
+        res
+    }
+
     fn fill(&mut self, db: &dyn HirDatabase, krate: CrateId) {
         let crate_def_map = db.crate_def_map(krate);
         for (_module_id, module_data) in crate_def_map.modules.iter() {
@@ -162,6 +208,11 @@ impl CrateImplDefs {
             )
     }
 
+    /// The traits with at least one impl in this collection.
+    pub fn traits<'a>(&'a self) -> impl Iterator<Item = TraitId> + 'a {
+        self.impls_by_trait.keys().copied()
+    }
+
     pub fn all_impls<'a>(&'a self) -> impl Iterator<Item = ImplId> + 'a {
         self.inherent_impls
             .values()
@@ -220,6 +271,23 @@ impl Ty {
             .collect();
         Some(res)
     }
+
+    /// Like `def_crates`, but yields the crates lazily, skipping the
+    /// intermediate `ArrayVec` for the common ADT case. Yields nothing where
+    /// `def_crates` returns `None`.
+    pub fn def_crates_iter(
+        &self,
+        db: &dyn HirDatabase,
+        cur_crate: CrateId,
+    ) -> impl Iterator<Item = CrateId> {
+        let (adt_crate, other_crates) = match self {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(def_id), .. }) => {
+                (Some(def_id.module(db.upcast()).krate), None)
+            }
+            _ => (None, self.def_crates(db, cur_crate)),
+        };
+        adt_crate.into_iter().chain(other_crates.into_iter().flatten())
+    }
 }
 /// Look up the method with the given name, returning the actual autoderefed
 /// receiver type (but without autoref applied yet).
@@ -246,6 +314,483 @@ pub(crate) fn lookup_method(
     )
 }
 
+/// Like `lookup_method`, but also returns how many autoderef steps were taken
+/// before the method was found, and the autoref applied after them.
+pub fn lookup_method_with_deref_count(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<(Ty, usize, Option<Mutability>, FunctionId)> {
+    let mut res = None;
+    iterate_method_call_candidates(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        Some(name),
+        None,
+        &mut |ty, deref_count, autoref, item| match item {
+            AssocItemId::FunctionId(func) => {
+                res = Some((ty.clone(), deref_count, autoref, func));
+                true
+            }
+            _ => false,
+        },
+    );
+    res
+}
+
+/// How well a method candidate fits a receiver, e.g. for ranking completions.
+/// Compares by field, in order: inherent methods rank before trait methods,
+/// then fewer autoderef steps, then no autoref; smaller is better.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Relevance {
+    pub from_trait: bool,
+    pub deref_count: usize,
+    pub autoref: bool,
+}
+
+/// Returns every method call candidate named `name` (or all of them), each
+/// annotated with its `Relevance`, sorted from most to least relevant.
+/// Candidates reachable in several ways are only reported for the first one,
+/// in method resolution order.
+pub fn ranked_method_candidates(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+) -> Vec<(AssocItemId, Relevance)> {
+    let mut seen = FxHashSet::default();
+    let mut res = Vec::new();
+    iterate_method_call_candidates(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        None,
+        &mut |_ty, deref_count, autoref, item| {
+            if seen.insert(item) {
+                let container = match item {
+                    AssocItemId::FunctionId(it) => it.lookup(db.upcast()).container,
+                    AssocItemId::ConstId(it) => it.lookup(db.upcast()).container,
+                    AssocItemId::TypeAliasId(it) => it.lookup(db.upcast()).container,
+                };
+                let from_trait = match container {
+                    AssocContainerId::ImplId(it) => db.impl_trait(it).is_some(),
+                    AssocContainerId::TraitId(_) => true,
+                    AssocContainerId::ContainerId(_) => false,
+                };
+                let relevance = Relevance { from_trait, deref_count, autoref: autoref.is_some() };
+                res.push((item, relevance));
+            }
+            false
+        },
+    );
+    res.sort_by_key(|&(_, relevance)| relevance);
+    res
+}
+
+/// A trait method found by `trait_only_method_candidates`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraitMethodCandidate {
+    pub item: AssocItemId,
+    pub trait_: TraitId,
+    /// The trait isn't in scope, so calling the method needs a `use`.
+    pub needs_import: bool,
+}
+
+/// Returns the method call candidates named `name` (or all of them) provided
+/// by traits, skipping inherent methods. Unlike normal method resolution, this
+/// also considers the traits implemented in `krate` and its dependencies which
+/// aren't in scope, so that their methods can be suggested with an import.
+pub fn trait_only_method_candidates(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+) -> Vec<TraitMethodCandidate> {
+    let mut all_traits = traits_in_scope.clone();
+    all_traits.extend(db.impls_in_crate(krate).traits());
+    all_traits.extend(db.impls_from_deps(krate).traits());
+
+    let ty = InEnvironment { value: ty.clone(), environment: env.clone() };
+    let mut res: Vec<TraitMethodCandidate> = Vec::new();
+    let mut seen = FxHashSet::default();
+    for self_ty in autoderef_method_receiver(db, krate, ty) {
+        iterate_trait_method_candidates(
+            &self_ty,
+            db,
+            env.clone(),
+            krate,
+            &all_traits,
+            name,
+            None,
+            None,
+            &mut |_ty, item| {
+                let func = match item {
+                    AssocItemId::FunctionId(it) => it,
+                    _ => return false,
+                };
+                let trait_ = match func.lookup(db.upcast()).container {
+                    AssocContainerId::TraitId(it) => it,
+                    _ => return false,
+                };
+                if db.function_data(func).has_self_param && seen.insert(item) {
+                    let needs_import = !traits_in_scope.contains(&trait_);
+                    res.push(TraitMethodCandidate { item, trait_, needs_import });
+                }
+                false
+            },
+        );
+    }
+    res
+}
+
+/// The outcome of a method lookup, see `diagnose_method_lookup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodLookupDiagnosis {
+    /// The method resolves to this function.
+    Found(FunctionId),
+    /// Only these traits, none of which is in scope, provide the method.
+    NeedsTraitImport(Vec<TraitId>),
+    NotFound,
+}
+
+/// Like `lookup_method`, but on failure also tells whether a trait which
+/// isn't in scope provides a method named `name`, to suggest importing it.
+pub fn diagnose_method_lookup(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> MethodLookupDiagnosis {
+    if let Some((_, func)) = lookup_method(ty, db, env.clone(), krate, traits_in_scope, name) {
+        return MethodLookupDiagnosis::Found(func);
+    }
+    let mut traits = Vec::new();
+    for candidate in trait_only_method_candidates(ty, db, env, krate, traits_in_scope, Some(name)) {
+        if candidate.needs_import && !traits.contains(&candidate.trait_) {
+            traits.push(candidate.trait_);
+        }
+    }
+    if traits.is_empty() {
+        MethodLookupDiagnosis::NotFound
+    } else {
+        MethodLookupDiagnosis::NeedsTraitImport(traits)
+    }
+}
+
+/// Like `lookup_method`, but returns the receiver as a fresh `Canonical`,
+/// independent of the variables of `ty`.
+pub fn lookup_method_canonical(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<(Canonical<Ty>, FunctionId)> {
+    let (receiver_ty, func) = lookup_method(ty, db, env, krate, traits_in_scope, name)?;
+    Some((recanonicalize(receiver_ty), func))
+}
+
+/// Renumbers the free bound variables of `ty` densely, in order of appearance.
+fn recanonicalize(ty: Ty) -> Canonical<Ty> {
+    let mut vars = Vec::new();
+    let value = ty.fold_binders(
+        &mut |ty, binders| match ty {
+            Ty::Bound(bound) if bound.debruijn >= binders => {
+                let index = match vars.iter().position(|&it| it == bound.index) {
+                    Some(index) => index,
+                    None => {
+                        vars.push(bound.index);
+                        vars.len() - 1
+                    }
+                };
+                Ty::Bound(BoundVar::new(bound.debruijn, index))
+            }
+            ty => ty,
+        },
+        DebruijnIndex::INNERMOST,
+    );
+    Canonical { num_vars: vars.len(), value }
+}
+
+/// Like `lookup_method`, but for a fully-known `ty`.
+///
+/// Returns `None` if `ty` contains inference or bound variables.
+pub fn lookup_method_for_ty(
+    ty: &Ty,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<(Ty, FunctionId)> {
+    let mut has_vars = false;
+    ty.walk(&mut |ty| {
+        if matches!(ty, Ty::Infer(_) | Ty::Bound(_)) {
+            has_vars = true;
+        }
+    });
+    if has_vars {
+        return None;
+    }
+    let canonical = Canonical { value: ty.clone(), num_vars: 0 };
+    lookup_method(&canonical, db, env, krate, traits_in_scope, name)
+}
+
+/// Like `lookup_method`, but only succeeds if the method resolves to an
+/// inherent method, and also returns the impl block defining it.
+pub fn lookup_inherent_method_with_impl(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<(Ty, ImplId, FunctionId)> {
+    let (receiver_ty, func) = lookup_method(ty, db, env, krate, traits_in_scope, name)?;
+    match func.lookup(db.upcast()).container {
+        AssocContainerId::ImplId(impl_id) => Some((receiver_ty, impl_id, func)),
+        AssocContainerId::TraitId(_) | AssocContainerId::ContainerId(_) => None,
+    }
+}
+
+/// Like `lookup_method`, but resolves as if `excluded` were not among
+/// `traits_in_scope`, e.g. to check whether importing it is necessary for the
+/// call to resolve.
+pub fn lookup_method_without_trait(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+    excluded: TraitId,
+) -> Option<(Ty, FunctionId)> {
+    let mut traits_in_scope = traits_in_scope.clone();
+    traits_in_scope.remove(&excluded);
+    lookup_method(ty, db, env, krate, &traits_in_scope, name)
+}
+
+/// Look up the associated function or constant with the given name, as in a
+/// path like `Vec::new` or `S::CONST`.
+pub fn lookup_path_assoc_item(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<(Ty, AssocItemId)> {
+    iterate_method_candidates(
+        ty,
+        db,
+        env,
+        krate,
+        &traits_in_scope,
+        Some(name),
+        LookupMode::Path,
+        |ty, item| match item {
+            AssocItemId::FunctionId(_) | AssocItemId::ConstId(_) => Some((ty.clone(), item)),
+            AssocItemId::TypeAliasId(_) => None,
+        },
+    )
+}
+
+/// Look up the function of a fully-qualified path like `<S as Trait>::name`,
+/// only considering the items of `trait_`. Without a trait, as in `S::name`,
+/// inherent items come first, then the items of any trait implemented in
+/// `krate` or its dependencies, whether it is in scope or not.
+pub fn lookup_qualified_method(
+    db: &dyn HirDatabase,
+    self_ty: &Canonical<Ty>,
+    trait_: Option<TraitId>,
+    name: &Name,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+) -> Option<(Ty, FunctionId)> {
+    let traits: FxHashSet<TraitId> = match trait_ {
+        Some(trait_) => std::iter::once(trait_).collect(),
+        None => {
+            let in_crate = db.impls_in_crate(krate);
+            let in_deps = db.impls_from_deps(krate);
+            in_crate.traits().chain(in_deps.traits()).collect()
+        }
+    };
+    iterate_method_candidates(
+        self_ty,
+        db,
+        env,
+        krate,
+        &traits,
+        Some(name),
+        LookupMode::Path,
+        |ty, item| {
+            let func = match item {
+                AssocItemId::FunctionId(func) => func,
+                _ => return None,
+            };
+            if let Some(trait_) = trait_ {
+                if func.lookup(db.upcast()).container != AssocContainerId::TraitId(trait_) {
+                    return None;
+                }
+            }
+            Some((ty.clone(), func))
+        },
+    )
+}
+
+/// Returns the crates searched for inherent impls of `ty`: the crate defining
+/// an ADT, or the crates with the lang item impls of a builtin type (e.g. both
+/// `core` and `alloc` for slices). Empty if `ty` can't have inherent impls.
+pub fn inherent_method_search_crates(
+    db: &dyn HirDatabase,
+    ty: &Ty,
+    krate: CrateId,
+) -> Vec<CrateId> {
+    ty.def_crates(db, krate).map_or_else(Vec::new, |it| it.into_iter().collect())
+}
+
+/// How a step of a deref chain was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerefKind {
+    /// Dereferencing a reference or raw pointer.
+    Builtin,
+    /// Going to the `Deref::Target` of a `Deref` impl.
+    Trait,
+}
+
+/// Returns the types `ty` successively derefs to, each with how it was
+/// reached, until neither a built-in deref nor a `Deref` impl applies (or the
+/// autoderef recursion limit is hit).
+pub fn deref_targets(
+    db: &dyn HirDatabase,
+    ty: &Ty,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+) -> Vec<(Ty, DerefKind)> {
+    let mut res = Vec::new();
+    let mut current = Canonical { value: ty.clone(), num_vars: 0 };
+    while res.len() < autoderef::AUTODEREF_RECURSION_LIMIT {
+        let (next, kind) = match current.value.builtin_deref() {
+            Some(derefed) => {
+                (Canonical { value: derefed, num_vars: current.num_vars }, DerefKind::Builtin)
+            }
+            None => {
+                let ty = InEnvironment { value: &current, environment: env.clone() };
+                match autoderef::deref_by_trait(db, krate, ty) {
+                    Some(derefed) => (derefed, DerefKind::Trait),
+                    None => break,
+                }
+            }
+        };
+        res.push((next.value.clone(), kind));
+        current = next;
+    }
+    res
+}
+
+/// Returns whether any inherent impl for `ty` defines a function, without
+/// resolving candidates. Impls are only matched by fingerprint, so for a
+/// generic type this also counts impls for other instantiations of it.
+pub fn has_inherent_methods(db: &dyn HirDatabase, ty: &Ty, krate: CrateId) -> bool {
+    let def_crates = match ty.def_crates(db, krate) {
+        Some(k) => k,
+        None => return false,
+    };
+    def_crates.into_iter().any(|krate| {
+        db.impls_in_crate(krate).lookup_impl_defs(ty).any(|impl_def| {
+            db.impl_data(impl_def)
+                .items
+                .iter()
+                .any(|item| matches!(item, AssocItemId::FunctionId(_)))
+        })
+    })
+}
+
+/// Finds an inherent method `name` on `ty` shadowing a trait method of the
+/// same name, i.e. both are candidates at the same autoderef and autoref
+/// step. Returns `(inherent, trait_method)`.
+pub fn shadowing_method(
+    db: &dyn HirDatabase,
+    ty: &Canonical<Ty>,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<(FunctionId, FunctionId)> {
+    let mut inherent = Vec::new();
+    let mut trait_methods = Vec::new();
+    iterate_method_call_candidates(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        Some(name),
+        None,
+        &mut |_ty, deref_count, autoref, item| {
+            if let AssocItemId::FunctionId(func) = item {
+                let step = (deref_count, autoref);
+                match func.lookup(db.upcast()).container {
+                    AssocContainerId::TraitId(_) => trait_methods.push((step, func)),
+                    _ => inherent.push((step, func)),
+                }
+            }
+            false
+        },
+    );
+    inherent.into_iter().find_map(|(step, inherent)| {
+        let (_, trait_method) = trait_methods.iter().find(|(it, _)| *it == step)?;
+        Some((inherent, *trait_method))
+    })
+}
+
+/// Returns whether the method call `name` on `ty` only resolves after an
+/// `&mut` autoref of the receiver, i.e. no by-value or `&self` method was found
+/// before it. Returns `None` if the method call doesn't resolve.
+pub fn requires_mut_receiver(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+) -> Option<bool> {
+    let mut res = None;
+    iterate_method_call_candidates(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        Some(name),
+        None,
+        &mut |_ty, _deref_count, autoref, item| match item {
+            AssocItemId::FunctionId(_) => {
+                res = Some(autoref == Some(Mutability::Mut));
+                true
+            }
+            _ => false,
+        },
+    );
+    res
+}
+
 /// Whether we're looking up a dotted method call (like `v.len()`) or a path
 /// (like `Vec::new`).
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -256,6 +801,12 @@ pub enum LookupMode {
     /// Looking up a path like `Vec::new` or `Vec::default`: We consider all
     /// candidates including associated constants, but don't do autoderef.
     Path,
+    /// Looking up a path used as a pattern, like `S::CONST` in `match s { S::CONST => () }`:
+    /// Like `Path`, but only associated constants can be matched against, so
+    /// methods and other functions are skipped. Enum variants and tuple
+    /// struct constructors are not associated items; they are resolved before
+    /// looking at the candidates here.
+    Pattern,
 }
 
 // This would be nicer if it just returned an iterator, but that runs into
@@ -269,7 +820,182 @@ pub fn iterate_method_candidates<T>(
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
     mode: LookupMode,
+    callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
+) -> Option<T> {
+    iterate_method_candidates_excluding(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        mode,
+        None,
+        callback,
+    )
+}
+
+/// Like `iterate_method_candidates`, but resolves as if the `exclude` impl
+/// didn't exist: its items are skipped, and if it is a trait impl for the
+/// self type, the trait's methods are skipped for that type as well.
+pub fn iterate_method_candidates_excluding<T>(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    mode: LookupMode,
+    exclude: Option<ImplId>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
+) -> Option<T> {
+    iterate_method_candidates_controlled(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        mode,
+        exclude,
+        |ty, item| callback(ty, item).into(),
+    )
+}
+
+/// A step of method candidate iteration, reported to the sink installed by
+/// `trace_method_resolution`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MethodResolutionStep {
+    /// Looking for methods on the receiver after `deref_count` autoderef steps.
+    Deref {
+        deref_count: usize,
+        ty: Ty,
+    },
+    /// Trying the current receiver with `autoref` applied.
+    Autoref(Option<Mutability>),
+    /// Checked whether the self type implements `trait_`.
+    TraitProbed {
+        trait_: TraitId,
+        implemented: bool,
+    },
+    CandidateAccepted(AssocItemId),
+    CandidateRejected(AssocItemId, RejectionReason),
+}
+
+/// Why a candidate with a matching name was not accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// A method call found an associated function without `self` or a const.
+    NoSelfParam,
+    /// The `self` parameter doesn't take the receiver type.
+    ReceiverMismatch,
+    /// The impl is for a different instantiation of the self type.
+    SelfTypeMismatch,
+}
+
+thread_local! {
+    static TRACE_SINK: RefCell<Option<Box<dyn FnMut(MethodResolutionStep)>>> = RefCell::new(None);
+}
+
+/// The number of `trace_method_resolution` calls in progress on any thread,
+/// so that `trace` can skip the thread-local lookup while nobody listens.
+static ACTIVE_TRACES: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs `f`, passing every step of method candidate iteration on this thread
+/// to `sink`, for debugging why a method does or doesn't resolve. Steps taken
+/// while `sink` itself runs, e.g. because it resolves methods, are not
+/// reported.
+pub fn trace_method_resolution<T>(
+    sink: impl FnMut(MethodResolutionStep) + 'static,
+    f: impl FnOnce() -> T,
+) -> T {
+    struct RestoreSink(Option<Box<dyn FnMut(MethodResolutionStep)>>);
+    impl Drop for RestoreSink {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            TRACE_SINK.with(|it| *it.borrow_mut() = prev);
+            ACTIVE_TRACES.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    ACTIVE_TRACES.fetch_add(1, Ordering::Relaxed);
+    let prev = TRACE_SINK.with(|it| it.replace(Some(Box::new(sink))));
+    let _restore = RestoreSink(prev);
+    f()
+}
+
+/// Reports a step to the tracing sink, if any; `step` is only computed then.
+pub(crate) fn trace(step: impl FnOnce() -> MethodResolutionStep) {
+    if ACTIVE_TRACES.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    // Take the sink out while it runs, so that tracing from inside it doesn't
+    // borrow `TRACE_SINK` twice.
+    let mut sink = match TRACE_SINK.with(|it| it.borrow_mut().take()) {
+        Some(it) => it,
+        None => return,
+    };
+    sink(step());
+    TRACE_SINK.with(|it| {
+        let mut it = it.borrow_mut();
+        if it.is_none() {
+            *it = Some(sink);
+        }
+    })
+}
+
+/// Tells method candidate iteration whether to go on to the next candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow<T> {
+    Continue,
+    Stop(T),
+}
+
+impl<T> From<Option<T>> for ControlFlow<T> {
+    fn from(it: Option<T>) -> ControlFlow<T> {
+        match it {
+            Some(it) => ControlFlow::Stop(it),
+            None => ControlFlow::Continue,
+        }
+    }
+}
+
+/// Like `iterate_method_candidates`, but the callback decides for each
+/// candidate whether to keep going, so callers can look at several
+/// candidates before settling on a result.
+pub fn iterate_method_candidates_with_control<T>(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    mode: LookupMode,
+    callback: impl FnMut(&Ty, AssocItemId) -> ControlFlow<T>,
+) -> Option<T> {
+    iterate_method_candidates_controlled(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        mode,
+        None,
+        callback,
+    )
+}
+
+fn iterate_method_candidates_controlled<T>(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    mode: LookupMode,
+    exclude: Option<ImplId>,
+    mut callback: impl FnMut(&Ty, AssocItemId) -> ControlFlow<T>,
 ) -> Option<T> {
     let mut slot = None;
     iterate_method_candidates_impl(
@@ -280,15 +1006,83 @@ pub fn iterate_method_candidates<T>(
         traits_in_scope,
         name,
         mode,
+        exclude,
         &mut |ty, item| {
             assert!(slot.is_none());
-            slot = callback(ty, item);
-            slot.is_some()
+            match callback(ty, item) {
+                ControlFlow::Continue => false,
+                ControlFlow::Stop(it) => {
+                    slot = Some(it);
+                    true
+                }
+            }
         },
     );
     slot
 }
 
+/// Counts the candidates with the given name, without collecting them and
+/// without stopping at the first one.
+pub fn count_method_candidates(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: &Name,
+    mode: LookupMode,
+) -> usize {
+    let mut count = 0;
+    iterate_method_candidates_impl(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        Some(name),
+        mode,
+        None,
+        &mut |_ty, _item| {
+            count += 1;
+            false
+        },
+    );
+    count
+}
+
+/// Calls `callback` once per distinct name of the methods callable on `ty`,
+/// e.g. for completion. If several impls or traits provide a method with the
+/// same name, the first one seen (in method resolution order) wins.
+pub fn iterate_unique_method_names(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    mut callback: impl FnMut(&Name, AssocItemId),
+) {
+    let mut seen = FxHashSet::default();
+    iterate_method_candidates_impl(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        None,
+        LookupMode::MethodCall,
+        None,
+        &mut |_ty, item| {
+            if let AssocItemId::FunctionId(f) = item {
+                let name = &db.function_data(f).name;
+                if seen.insert(name.clone()) {
+                    callback(name, item);
+                }
+            }
+            false
+        },
+    );
+}
+
 fn iterate_method_candidates_impl(
     ty: &Canonical<Ty>,
     db: &dyn HirDatabase,
@@ -297,49 +1091,20 @@ fn iterate_method_candidates_impl(
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
     mode: LookupMode,
+    exclude: Option<ImplId>,
     callback: &mut dyn FnMut(&Ty, AssocItemId) -> bool,
 ) -> bool {
     match mode {
-        LookupMode::MethodCall => {
-            // For method calls, rust first does any number of autoderef, and then one
-            // autoref (i.e. when the method takes &self or &mut self). We just ignore
-            // the autoref currently -- when we find a method matching the given name,
-            // we assume it fits.
-
-            // Also note that when we've got a receiver like &S, even if the method we
-            // find in the end takes &self, we still do the autoderef step (just as
-            // rustc does an autoderef and then autoref again).
-            let ty = InEnvironment { value: ty.clone(), environment: env.clone() };
-
-            // We have to be careful about the order we're looking at candidates
-            // in here. Consider the case where we're resolving `x.clone()`
-            // where `x: &Vec<_>`. This resolves to the clone method with self
-            // type `Vec<_>`, *not* `&_`. I.e. we need to consider methods where
-            // the receiver type exactly matches before cases where we have to
-            // do autoref. But in the autoderef steps, the `&_` self type comes
-            // up *before* the `Vec<_>` self type.
-            //
-            // On the other hand, we don't want to just pick any by-value method
-            // before any by-autoref method; it's just that we need to consider
-            // the methods by autoderef order of *receiver types*, not *self
-            // types*.
-
-            let deref_chain = autoderef_method_receiver(db, krate, ty);
-            for i in 0..deref_chain.len() {
-                if iterate_method_candidates_with_autoref(
-                    &deref_chain[i..],
-                    db,
-                    env.clone(),
-                    krate,
-                    traits_in_scope,
-                    name,
-                    callback,
-                ) {
-                    return true;
-                }
-            }
-            false
-        }
+        LookupMode::MethodCall => iterate_method_call_candidates(
+            ty,
+            db,
+            env,
+            krate,
+            traits_in_scope,
+            name,
+            exclude,
+            &mut |ty, _deref_count, _autoref, item| callback(ty, item),
+        ),
         LookupMode::Path => {
             // No autoderef for path lookups
             iterate_method_candidates_for_self_ty(
@@ -349,10 +1114,78 @@ fn iterate_method_candidates_impl(
                 krate,
                 traits_in_scope,
                 name,
+                exclude,
                 callback,
             )
         }
+        LookupMode::Pattern => iterate_method_candidates_for_self_ty(
+            &ty,
+            db,
+            env,
+            krate,
+            traits_in_scope,
+            name,
+            exclude,
+            &mut |ty, item| match item {
+                AssocItemId::ConstId(_) => callback(ty, item),
+                AssocItemId::FunctionId(_) | AssocItemId::TypeAliasId(_) => false,
+            },
+        ),
+    }
+}
+
+/// Iterates the candidates for a method call, additionally passing the number
+/// of autoderef steps and the autoref after which each candidate was found.
+fn iterate_method_call_candidates(
+    ty: &Canonical<Ty>,
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    exclude: Option<ImplId>,
+    callback: &mut dyn FnMut(&Ty, usize, Option<Mutability>, AssocItemId) -> bool,
+) -> bool {
+    // For method calls, rust first does any number of autoderef, and then one
+    // autoref (i.e. when the method takes &self or &mut self). We just ignore
+    // the autoref currently -- when we find a method matching the given name,
+    // we assume it fits.
+
+    // Also note that when we've got a receiver like &S, even if the method we
+    // find in the end takes &self, we still do the autoderef step (just as
+    // rustc does an autoderef and then autoref again).
+    let ty = InEnvironment { value: ty.clone(), environment: env.clone() };
+
+    // We have to be careful about the order we're looking at candidates
+    // in here. Consider the case where we're resolving `x.clone()`
+    // where `x: &Vec<_>`. This resolves to the clone method with self
+    // type `Vec<_>`, *not* `&_`. I.e. we need to consider methods where
+    // the receiver type exactly matches before cases where we have to
+    // do autoref. But in the autoderef steps, the `&_` self type comes
+    // up *before* the `Vec<_>` self type.
+    //
+    // On the other hand, we don't want to just pick any by-value method
+    // before any by-autoref method; it's just that we need to consider
+    // the methods by autoderef order of *receiver types*, not *self
+    // types*.
+
+    let deref_chain = autoderef_method_receiver(db, krate, ty);
+    for i in 0..deref_chain.len() {
+        trace(|| MethodResolutionStep::Deref { deref_count: i, ty: deref_chain[i].value.clone() });
+        if iterate_method_candidates_with_autoref(
+            &deref_chain[i..],
+            db,
+            env.clone(),
+            krate,
+            traits_in_scope,
+            name,
+            exclude,
+            &mut |ty, autoref, item| callback(ty, i, autoref, item),
+        ) {
+            return true;
+        }
     }
+    false
 }
 
 fn iterate_method_candidates_with_autoref(
@@ -362,8 +1195,10 @@ fn iterate_method_candidates_with_autoref(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
-    mut callback: &mut dyn FnMut(&Ty, AssocItemId) -> bool,
+    exclude: Option<ImplId>,
+    callback: &mut dyn FnMut(&Ty, Option<Mutability>, AssocItemId) -> bool,
 ) -> bool {
+    trace(|| MethodResolutionStep::Autoref(None));
     if iterate_method_candidates_by_receiver(
         &deref_chain[0],
         &deref_chain[1..],
@@ -372,10 +1207,12 @@ fn iterate_method_candidates_with_autoref(
         krate,
         &traits_in_scope,
         name,
-        &mut callback,
+        exclude,
+        &mut |ty, item| callback(ty, None, item),
     ) {
         return true;
     }
+    trace(|| MethodResolutionStep::Autoref(Some(Mutability::Shared)));
     let refed = Canonical {
         num_vars: deref_chain[0].num_vars,
         value: Ty::apply_one(TypeCtor::Ref(Mutability::Shared), deref_chain[0].value.clone()),
@@ -388,10 +1225,12 @@ fn iterate_method_candidates_with_autoref(
         krate,
         &traits_in_scope,
         name,
-        &mut callback,
+        exclude,
+        &mut |ty, item| callback(ty, Some(Mutability::Shared), item),
     ) {
         return true;
     }
+    trace(|| MethodResolutionStep::Autoref(Some(Mutability::Mut)));
     let ref_muted = Canonical {
         num_vars: deref_chain[0].num_vars,
         value: Ty::apply_one(TypeCtor::Ref(Mutability::Mut), deref_chain[0].value.clone()),
@@ -404,7 +1243,8 @@ fn iterate_method_candidates_with_autoref(
         krate,
         &traits_in_scope,
         name,
-        &mut callback,
+        exclude,
+        &mut |ty, item| callback(ty, Some(Mutability::Mut), item),
     ) {
         return true;
     }
@@ -419,13 +1259,22 @@ fn iterate_method_candidates_by_receiver(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
+    exclude: Option<ImplId>,
     mut callback: &mut dyn FnMut(&Ty, AssocItemId) -> bool,
 ) -> bool {
     // We're looking for methods with *receiver* type receiver_ty. These could
     // be found in any of the derefs of receiver_ty, so we have to go through
     // that.
     for self_ty in std::iter::once(receiver_ty).chain(rest_of_deref_chain) {
-        if iterate_inherent_methods(self_ty, db, name, Some(receiver_ty), krate, &mut callback) {
+        if iterate_inherent_methods(
+            self_ty,
+            db,
+            name,
+            Some(receiver_ty),
+            krate,
+            exclude,
+            &mut callback,
+        ) {
             return true;
         }
     }
@@ -438,6 +1287,7 @@ fn iterate_method_candidates_by_receiver(
             &traits_in_scope,
             name,
             Some(receiver_ty),
+            exclude,
             &mut callback,
         ) {
             return true;
@@ -453,12 +1303,23 @@ fn iterate_method_candidates_for_self_ty(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
+    exclude: Option<ImplId>,
     mut callback: &mut dyn FnMut(&Ty, AssocItemId) -> bool,
 ) -> bool {
-    if iterate_inherent_methods(self_ty, db, name, None, krate, &mut callback) {
+    if iterate_inherent_methods(self_ty, db, name, None, krate, exclude, &mut callback) {
         return true;
     }
-    iterate_trait_method_candidates(self_ty, db, env, krate, traits_in_scope, name, None, callback)
+    iterate_trait_method_candidates(
+        self_ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        None,
+        exclude,
+        callback,
+    )
 }
 
 fn iterate_trait_method_candidates(
@@ -469,13 +1330,22 @@ fn iterate_trait_method_candidates(
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
     receiver_ty: Option<&Canonical<Ty>>,
+    exclude: Option<ImplId>,
     callback: &mut dyn FnMut(&Ty, AssocItemId) -> bool,
 ) -> bool {
+    // if the excluded impl is a trait impl for this type, pretend the trait
+    // isn't implemented
+    let excluded_trait = exclude.and_then(|impl_id| {
+        let trait_ = db.impl_trait(impl_id)?.value.trait_;
+        inherent_impl_substs(db, impl_id, self_ty).map(|_| trait_)
+    });
     // if ty is `dyn Trait`, the trait doesn't need to be in scope
-    let inherent_trait =
-        self_ty.value.dyn_trait().into_iter().flat_map(|t| all_super_traits(db.upcast(), t));
+    let inherent_trait = dyn_trait_and_super_traits(db, &self_ty.value);
     let env_traits = if let Ty::Placeholder(_) = self_ty.value {
-        // if we have `T: Trait` in the param env, the trait doesn't need to be in scope
+        // if we have `T: Trait` in the param env, the trait doesn't need to be in scope.
+        // This also covers `Self::method()` in trait default bodies, where
+        // the env contains `Self: Trait`.
+        test_utils::mark::hit!(method_candidates_from_env_traits);
         env.trait_predicates_for_self_ty(&self_ty.value)
             .map(|tr| tr.trait_)
             .flat_map(|t| all_super_traits(db.upcast(), t))
@@ -483,26 +1353,39 @@ fn iterate_trait_method_candidates(
     } else {
         Vec::new()
     };
-    let traits =
-        inherent_trait.chain(env_traits.into_iter()).chain(traits_in_scope.iter().copied());
+    let traits = inherent_trait
+        .into_iter()
+        .chain(env_traits.into_iter())
+        .chain(traits_in_scope.iter().copied());
     'traits: for t in traits {
+        if Some(t) == excluded_trait {
+            continue;
+        }
         let data = db.trait_data(t);
 
         // we'll be lazy about checking whether the type implements the
         // trait, but if we find out it doesn't, we'll skip the rest of the
         // iteration
         let mut known_implemented = false;
-        for (_name, item) in data.items.iter() {
+        for (item_name, item) in data.items.iter() {
+            // trait items are stored with their names, so there is no need
+            // for an index like `impl_items_by_name` to skip the others
+            if name.map_or(false, |name| item_name != name) {
+                continue;
+            }
             if !is_valid_candidate(db, name, receiver_ty, *item, self_ty) {
                 continue;
             }
             if !known_implemented {
                 let goal = generic_implements_goal(db, env.clone(), t, self_ty.clone());
-                if db.trait_solve(krate, goal).is_none() {
+                let implemented = db.trait_solve(krate, goal).is_some();
+                trace(|| MethodResolutionStep::TraitProbed { trait_: t, implemented });
+                if !implemented {
                     continue 'traits;
                 }
             }
             known_implemented = true;
+            trace(|| MethodResolutionStep::CandidateAccepted(*item));
             if callback(&self_ty.value, *item) {
                 return true;
             }
@@ -511,12 +1394,50 @@ fn iterate_trait_method_candidates(
     false
 }
 
+fn dyn_trait_and_super_traits(db: &dyn HirDatabase, self_ty: &Ty) -> Vec<TraitId> {
+    self_ty.dyn_trait().map(|t| all_super_traits(db.upcast(), t)).unwrap_or_default()
+}
+
+/// Returns the methods callable on a `dyn Trait` `self_ty` through the trait
+/// and its supertraits, none of which need to be in scope.
+pub fn dyn_trait_methods(
+    db: &dyn HirDatabase,
+    self_ty: &Ty,
+    name: Option<&Name>,
+) -> Vec<AssocItemId> {
+    dyn_trait_and_super_traits(db, self_ty)
+        .into_iter()
+        .flat_map(|t| db.trait_data(t).items.clone())
+        .filter_map(|(_name, item)| match item {
+            AssocItemId::FunctionId(f) => Some(f),
+            _ => None,
+        })
+        .filter(|&f| name.map_or(true, |name| &db.function_data(f).name == name))
+        .map(AssocItemId::FunctionId)
+        .collect()
+}
+
+/// Returns the associated items of `trait_` and all its supertraits. Items
+/// of a trait shadow same-named items of its supertraits.
+pub fn trait_items_including_supertraits(
+    db: &dyn HirDatabase,
+    trait_: TraitId,
+) -> Vec<(Name, AssocItemId)> {
+    let mut seen = FxHashSet::default();
+    all_super_traits(db.upcast(), trait_)
+        .into_iter()
+        .flat_map(|t| db.trait_data(t).items.clone())
+        .filter(|(name, _)| seen.insert(name.clone()))
+        .collect()
+}
+
 fn iterate_inherent_methods(
     self_ty: &Canonical<Ty>,
     db: &dyn HirDatabase,
     name: Option<&Name>,
     receiver_ty: Option<&Canonical<Ty>>,
     krate: CrateId,
+    exclude: Option<ImplId>,
     callback: &mut dyn FnMut(&Ty, AssocItemId) -> bool,
 ) -> bool {
     let def_crates = match self_ty.value.def_crates(db, krate) {
@@ -527,7 +1448,23 @@ fn iterate_inherent_methods(
         let impls = db.impls_in_crate(krate);
 
         for impl_def in impls.lookup_impl_defs(&self_ty.value) {
-            for &item in db.impl_data(impl_def).items.iter() {
+            if Some(impl_def) == exclude {
+                continue;
+            }
+            let impl_data;
+            let items_by_name;
+            let items = match name {
+                Some(name) => {
+                    test_utils::mark::hit!(inherent_impl_items_by_name);
+                    items_by_name = db.impl_items_by_name(impl_def);
+                    items_by_name.get(name).map_or(&[][..], |it| it.as_slice())
+                }
+                None => {
+                    impl_data = db.impl_data(impl_def);
+                    impl_data.items.as_slice()
+                }
+            };
+            for &item in items {
                 if !is_valid_candidate(db, name, receiver_ty, item, self_ty) {
                     continue;
                 }
@@ -537,8 +1474,15 @@ fn iterate_inherent_methods(
                 // check it here
                 if receiver_ty.is_none() && inherent_impl_substs(db, impl_def, self_ty).is_none() {
                     test_utils::mark::hit!(impl_self_type_match_without_receiver);
+                    trace(|| {
+                        MethodResolutionStep::CandidateRejected(
+                            item,
+                            RejectionReason::SelfTypeMismatch,
+                        )
+                    });
                     continue;
                 }
+                trace(|| MethodResolutionStep::CandidateAccepted(item));
                 if callback(&self_ty.value, item) {
                     return true;
                 }
@@ -548,6 +1492,51 @@ fn iterate_inherent_methods(
     false
 }
 
+/// Maps the names of the functions and constants of `impl_id` to those items,
+/// in declaration order. Only these can be method resolution candidates, so
+/// lookups by name can skip scanning the whole impl.
+pub(crate) fn impl_items_by_name_query(
+    db: &dyn HirDatabase,
+    impl_id: ImplId,
+) -> Arc<FxHashMap<Name, Vec<AssocItemId>>> {
+    let mut res = FxHashMap::<Name, Vec<AssocItemId>>::default();
+    for &item in db.impl_data(impl_id).items.iter() {
+        let name = match item {
+            AssocItemId::FunctionId(f) => db.function_data(f).name.clone(),
+            AssocItemId::ConstId(c) => match db.const_data(c).name.clone() {
+                Some(name) => name,
+                None => continue,
+            },
+            AssocItemId::TypeAliasId(_) => continue,
+        };
+        res.entry(name).or_default().push(item);
+    }
+    Arc::new(res)
+}
+
+/// Returns the output of built-in indexing of `ty` (possibly behind
+/// references), without going through the `Index` trait: the element type
+/// for slices and arrays, and `str` for `str`.
+///
+/// The index type is not taken into account. Slices and arrays are assumed to
+/// be indexed by `usize`, and `str`, which can only be indexed by ranges, by a
+/// range. Everything else, including user `Index` impls, is left to
+/// `resolve_indexing_op`.
+pub fn builtin_index_output(ty: &Ty) -> Option<Ty> {
+    let mut ty = ty;
+    while let Ty::Apply(ApplicationTy { ctor: TypeCtor::Ref(_), parameters }) = ty {
+        ty = parameters.as_single();
+    }
+    match ty {
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Slice, parameters })
+        | Ty::Apply(ApplicationTy { ctor: TypeCtor::Array, parameters }) => {
+            Some(parameters.as_single().clone())
+        }
+        Ty::Apply(ApplicationTy { ctor: TypeCtor::Str, .. }) => Some(ty.clone()),
+        _ => None,
+    }
+}
+
 /// Returns the self type for the index trait call.
 pub fn resolve_indexing_op(
     db: &dyn HirDatabase,
@@ -567,6 +1556,55 @@ pub fn resolve_indexing_op(
     None
 }
 
+/// An operator that desugars to a call of a trait method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Binary(BinaryOp),
+    /// `a[i]`, which isn't a `BinaryOp` in HIR.
+    Index,
+}
+
+impl From<BinaryOp> for Operator {
+    fn from(op: BinaryOp) -> Operator {
+        Operator::Binary(op)
+    }
+}
+
+/// Finds the operator trait and the method of it that `op` resolves to when
+/// applied to an operand of type `ty`, e.g. `Add::add` for `a + b`. Only an
+/// indexed operand is autoderefed; binary operators must be implemented for
+/// `ty` itself.
+pub fn operator_method(
+    db: &dyn HirDatabase,
+    ty: &Canonical<Ty>,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    op: Operator,
+) -> Option<(TraitId, FunctionId)> {
+    let (lang_item, method) = match op {
+        Operator::Binary(op) => crate::op::binary_op_lang_item(op)?,
+        Operator::Index => ("index", name![index]),
+    };
+    let trait_ = match db.lang_item(krate, lang_item.into())? {
+        LangItemTarget::TraitId(it) => it,
+        _ => return None,
+    };
+    match op {
+        Operator::Index => {
+            resolve_indexing_op(db, ty, env, krate, trait_)?;
+        }
+        Operator::Binary(_) => {
+            let goal = generic_implements_goal(db, env, trait_, ty.clone());
+            db.trait_solve(krate, goal)?;
+        }
+    }
+    let func = db.trait_data(trait_).items.iter().find_map(|(name, item)| match item {
+        AssocItemId::FunctionId(f) if *name == method => Some(*f),
+        _ => None,
+    })?;
+    Some((trait_, func))
+}
+
 fn is_valid_candidate(
     db: &dyn HirDatabase,
     name: Option<&Name>,
@@ -583,22 +1621,35 @@ fn is_valid_candidate(
                 }
             }
             if let Some(receiver_ty) = receiver_ty {
+                let reject = |reason| {
+                    trace(|| MethodResolutionStep::CandidateRejected(item, reason));
+                    false
+                };
                 if !data.has_self_param {
-                    return false;
+                    return reject(RejectionReason::NoSelfParam);
                 }
                 let transformed_receiver_ty = match transform_receiver_ty(db, m, self_ty) {
                     Some(ty) => ty,
-                    None => return false,
+                    None => return reject(RejectionReason::SelfTypeMismatch),
                 };
                 if transformed_receiver_ty != receiver_ty.value {
-                    return false;
+                    return reject(RejectionReason::ReceiverMismatch);
                 }
             }
             true
         }
         AssocItemId::ConstId(c) => {
             let data = db.const_data(c);
-            name.map_or(true, |name| data.name.as_ref() == Some(name)) && receiver_ty.is_none()
+            if !name.map_or(true, |name| data.name.as_ref() == Some(name)) {
+                return false;
+            }
+            if receiver_ty.is_some() {
+                trace(|| {
+                    MethodResolutionStep::CandidateRejected(item, RejectionReason::NoSelfParam)
+                });
+                return false;
+            }
+            true
         }
         _ => false,
     }
@@ -645,6 +1696,23 @@ fn fallback_bound_vars(s: Substs, num_vars_to_keep: usize) -> Substs {
     )
 }
 
+/// Returns the type of the `self` parameter of `function_id` when called on
+/// `self_ty`, with the impl's generics substituted, e.g. `&mut Foo<i32>` for a
+/// `&mut self` method of `impl<T> Foo<T>` called on `Foo<i32>`.
+///
+/// Returns `None` if the function has no `self` parameter or its impl doesn't
+/// apply to `self_ty`.
+pub fn method_receiver_type(
+    db: &dyn HirDatabase,
+    function_id: FunctionId,
+    self_ty: &Canonical<Ty>,
+) -> Option<Ty> {
+    if !db.function_data(function_id).has_self_param {
+        return None;
+    }
+    transform_receiver_ty(db, function_id, self_ty)
+}
+
 fn transform_receiver_ty(
     db: &dyn HirDatabase,
     function_id: FunctionId,
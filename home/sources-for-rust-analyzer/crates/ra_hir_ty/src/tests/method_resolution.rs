@@ -1,7 +1,51 @@
-use super::{infer, type_at, type_at_pos};
-use crate::test_db::TestDB;
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+use hir_def::{
+    child_by_source::ChildBySource,
+    db::DefDatabase,
+    expr::{ArithOp, BinaryOp, LogicOp},
+    keys,
+    resolver::HasResolver,
+    type_ref::Mutability,
+    AssocContainerId, AssocItemId, ImplId, Lookup, TraitId,
+};
+use hir_expand::{
+    name::{AsName, Name},
+    InFile,
+};
 use insta::assert_snapshot;
-use ra_db::fixture::WithFixture;
+use ra_db::{
+    fixture::WithFixture,
+    salsa::{InternId, InternKey},
+    CrateId, SourceDatabase,
+};
+use ra_syntax::{
+    algo,
+    ast::{self, make},
+    AstNode,
+};
+use rustc_hash::FxHashSet;
+
+use super::{infer, type_at, type_at_pos};
+use crate::{
+    db::HirDatabase,
+    display::HirDisplay,
+    method_resolution::{
+        builtin_index_output, count_method_candidates, deref_targets, diagnose_method_lookup,
+        dyn_trait_methods, fingerprint_for_method_lookup, has_inherent_methods,
+        inherent_method_search_crates, iterate_method_candidates,
+        iterate_method_candidates_excluding, iterate_method_candidates_with_control,
+        iterate_unique_method_names, lookup_inherent_method_with_impl, lookup_method_canonical,
+        lookup_method_for_ty, lookup_method_with_deref_count, lookup_method_without_trait,
+        lookup_path_assoc_item, lookup_qualified_method, method_receiver_type, operator_method,
+        ranked_method_candidates, requires_mut_receiver, shadowing_method, trace,
+        trace_method_resolution, trait_only_method_candidates, ControlFlow, CrateImplDefs,
+        DerefKind, LookupMode, MethodLookupDiagnosis, MethodResolutionStep, Operator, Relevance,
+        TraitMethodCandidate, TyFingerprint,
+    },
+    test_db::TestDB,
+    BoundVar, Canonical, DebruijnIndex, IntTy, TraitEnvironment, Ty, TypeCtor,
+};
 
 #[test]
 fn infer_slice_method() {
@@ -928,6 +972,96 @@ fn test() { S2.into()<|>; }
     assert_eq!(t, "{unknown}");
 }
 
+#[test]
+fn method_resolution_through_type_alias() {
+    test_utils::mark::check!(type_alias_expanded);
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Hasher;
+struct HashSet<T, H>(T, H);
+impl<T, H> HashSet<T, H> {
+    fn len(&self) -> usize { 0 }
+}
+type FxHashSet<T> = HashSet<T, Hasher>;
+
+fn test(set: FxHashSet<u32>) {
+    set.len()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "usize");
+}
+
+#[test]
+fn method_resolution_self_path_to_supertrait_in_default_body() {
+    test_utils::mark::check!(method_candidates_from_env_traits);
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Base {
+    fn base() -> u32;
+}
+trait Derived: Base {
+    fn derived() -> u32 {
+        Self::base()<|>
+    }
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn builtin_index_output_of_slices_arrays_and_str() {
+    let i32 = Ty::simple(TypeCtor::Int(IntTy::i32()));
+    let str = Ty::simple(TypeCtor::Str);
+    let slice = Ty::apply_one(TypeCtor::Slice, i32.clone());
+    let array = Ty::apply_one(TypeCtor::Array, i32.clone());
+    assert_eq!(builtin_index_output(&slice), Some(i32.clone()));
+    assert_eq!(builtin_index_output(&array), Some(i32.clone()));
+    assert_eq!(
+        builtin_index_output(&Ty::apply_one(TypeCtor::Ref(Mutability::Shared), array)),
+        Some(i32.clone())
+    );
+    assert_eq!(builtin_index_output(&str), Some(str.clone()));
+    assert_eq!(
+        builtin_index_output(&Ty::apply_one(TypeCtor::Ref(Mutability::Shared), str.clone())),
+        Some(str)
+    );
+    assert_eq!(builtin_index_output(&i32), None);
+}
+
+#[test]
+fn user_index_impls_use_the_index_trait() {
+    let t = type_at(
+        r#"
+//- /main.rs crate:main deps:std
+struct Bar;
+struct Foo;
+
+impl std::ops::Index<usize> for Bar {
+    type Output = Foo;
+}
+
+fn test() {
+    let a = Bar;
+    a[1]<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use ops::*;
+mod ops {
+    #[lang = "index"]
+    pub trait Index<Idx> {
+        type Output;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "Foo");
+}
+
 #[test]
 fn method_resolution_overloaded_method() {
     test_utils::mark::check!(impl_self_type_match_without_receiver);
@@ -1073,3 +1207,1233 @@ fn test(d: &dyn Trait) {
     "###
     );
 }
+
+/// The type of the expression at `<|>`, together with everything needed to
+/// call into method resolution from the function containing it.
+struct MethodResolutionCtx {
+    db: TestDB,
+    ty: Canonical<Ty>,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: FxHashSet<TraitId>,
+}
+
+impl MethodResolutionCtx {
+    fn new(ra_fixture: &str) -> MethodResolutionCtx {
+        let (db, pos) = TestDB::with_position(ra_fixture);
+        let file = db.parse(pos.file_id).ok().unwrap();
+        let expr = algo::find_node_at_offset::<ast::Expr>(file.syntax(), pos.offset).unwrap();
+        let fn_def = expr.syntax().ancestors().find_map(ast::FnDef::cast).unwrap();
+        let module = db.module_for_file(pos.file_id);
+        let func = *module.child_by_source(&db)[keys::FUNCTION]
+            .get(&InFile::new(pos.file_id.into(), fn_def))
+            .unwrap();
+
+        let (_body, source_map) = db.body_with_source_map(func.into());
+        let expr_id = source_map
+            .node_expr(InFile::new(pos.file_id.into(), &expr))
+            .expect("can't find expression");
+        let ty = db.infer(func.into())[expr_id].clone();
+
+        let resolver = func.resolver(&db);
+        let env = TraitEnvironment::lower(&db, &resolver);
+        let traits_in_scope = resolver.traits_in_scope(&db);
+        MethodResolutionCtx {
+            db,
+            ty: Canonical { value: ty, num_vars: 0 },
+            env,
+            krate: module.krate,
+            traits_in_scope,
+        }
+    }
+}
+
+fn name(text: &str) -> Name {
+    make::name_ref(text).as_name()
+}
+
+#[test]
+fn count_method_candidates_does_not_stop_at_first() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S { fn foo(&self) {} }
+impl S { fn foo(&self) {} }
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let count = count_method_candidates(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        &name("foo"),
+        LookupMode::MethodCall,
+    );
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn lookup_path_assoc_item_finds_function() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct Vec<T> {}
+impl<T> Vec<T> {
+    fn new() -> Self { Vec {} }
+}
+fn test(v: Vec<u32>) { v<|>; }
+"#,
+    );
+    let (ty, item) = lookup_path_assoc_item(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        &name("new"),
+    )
+    .unwrap();
+    assert_eq!(ty.display(&ctx.db).to_string(), "Vec<u32>");
+    match item {
+        AssocItemId::FunctionId(f) => assert_eq!(ctx.db.function_data(f).name, name("new")),
+        _ => panic!("expected a function, got {:?}", item),
+    }
+}
+
+#[test]
+fn lookup_path_assoc_item_finds_const() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    const ANSWER: u32 = 42;
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let (_ty, item) = lookup_path_assoc_item(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        &name("ANSWER"),
+    )
+    .unwrap();
+    match item {
+        AssocItemId::ConstId(c) => assert_eq!(ctx.db.const_data(c).name, Some(name("ANSWER"))),
+        _ => panic!("expected a const, got {:?}", item),
+    }
+}
+
+#[test]
+fn requires_mut_receiver_only_mut_method() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn by_mut(&mut self) {}
+    fn by_ref(&self) {}
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let requires_mut = |method: &str| {
+        requires_mut_receiver(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name(method),
+        )
+    };
+    assert_eq!(requires_mut("by_mut"), Some(true));
+    assert_eq!(requires_mut("by_ref"), Some(false));
+    assert_eq!(requires_mut("missing"), None);
+}
+
+#[test]
+fn lookup_method_for_concrete_ty() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn foo(&self) -> u32 { 0 }
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let lookup = |ty: &Ty| {
+        lookup_method_for_ty(
+            ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name("foo"),
+        )
+    };
+    let (receiver_ty, func) = lookup(&ctx.ty.value).unwrap();
+    assert_eq!(receiver_ty, ctx.ty.value);
+    assert_eq!(ctx.db.function_data(func).name.to_string(), "foo");
+    assert_eq!(lookup(&Ty::Bound(BoundVar::new(DebruijnIndex::INNERMOST, 0))), None);
+}
+
+#[test]
+fn excluded_impl_is_skipped() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn foo(&self) {}
+}
+impl S {
+    fn foo(&self) {}
+}
+trait Tr {
+    fn bar(&self);
+}
+impl Tr for S {
+    fn bar(&self) {}
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let resolve = |method: &str, exclude: Option<ImplId>| {
+        iterate_method_candidates_excluding(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            Some(&name(method)),
+            LookupMode::MethodCall,
+            exclude,
+            |_ty, item| match item {
+                AssocItemId::FunctionId(func) => Some(func),
+                _ => None,
+            },
+        )
+    };
+
+    let impls = ctx.db.impls_in_crate(ctx.krate);
+    let inherent_impls = impls.lookup_impl_defs(&ctx.ty.value).collect::<Vec<_>>();
+    assert_eq!(inherent_impls.len(), 2);
+    for (excluded, other) in vec![(0, 1), (1, 0)] {
+        let func = resolve("foo", Some(inherent_impls[excluded])).unwrap();
+        assert_eq!(func.lookup(&ctx.db).container, AssocContainerId::ImplId(inherent_impls[other]));
+    }
+
+    let trait_impl = impls.all_impls().find(|it| !inherent_impls.contains(it)).unwrap();
+    assert!(resolve("bar", None).is_some());
+    assert!(resolve("bar", Some(inherent_impls[0])).is_some());
+    assert_eq!(resolve("bar", Some(trait_impl)), None);
+}
+
+#[test]
+fn def_crates_iter_matches_def_crates() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs crate:main deps:core
+struct S;
+fn test(s: S) { s<|>; }
+
+//- /core.rs crate:core
+#[lang = "bool"]
+impl bool {}
+"#,
+    );
+    for ty in vec![ctx.ty.value.clone(), Ty::simple(TypeCtor::Bool)] {
+        let def_crates = ty.def_crates(&ctx.db, ctx.krate).unwrap().to_vec();
+        assert_eq!(def_crates.len(), 1);
+        assert_eq!(ty.def_crates_iter(&ctx.db, ctx.krate).collect::<Vec<_>>(), def_crates);
+    }
+    assert_ne!(
+        ctx.ty.value.def_crates_iter(&ctx.db, ctx.krate).collect::<Vec<_>>(),
+        Ty::simple(TypeCtor::Bool).def_crates_iter(&ctx.db, ctx.krate).collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn inherent_method_search_crates_of_str() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs crate:main deps:core,alloc
+struct S;
+fn test(s: S) { s<|>; }
+
+//- /core.rs crate:core
+#[lang = "str"]
+impl str {}
+
+//- /alloc.rs crate:alloc deps:core
+#[lang = "str_alloc"]
+impl str {}
+"#,
+    );
+    let crate_graph = ctx.db.crate_graph();
+    let mut crates: Vec<String> =
+        inherent_method_search_crates(&ctx.db, &Ty::simple(TypeCtor::Str), ctx.krate)
+            .into_iter()
+            .map(|krate| crate_graph[krate].display_name.as_ref().unwrap().to_string())
+            .collect();
+    crates.sort();
+    assert_eq!(crates, vec!["alloc", "core"]);
+    assert!(
+        inherent_method_search_crates(&ctx.db, &Ty::simple(TypeCtor::Never), ctx.krate).is_empty()
+    );
+}
+
+#[test]
+fn dyn_trait_methods_include_supertraits() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+mod iter {
+    pub trait Base {
+        fn base(&self);
+    }
+    pub trait Iterator: Base {
+        type Item;
+        fn next(&mut self);
+        fn count(self);
+    }
+}
+fn test(it: &dyn iter::Iterator<Item = u32>) { *it<|>; }
+"#,
+    );
+    let method_names = |name: Option<&Name>| {
+        let mut names = dyn_trait_methods(&ctx.db, &ctx.ty.value, name)
+            .into_iter()
+            .map(|item| match item {
+                AssocItemId::FunctionId(func) => ctx.db.function_data(func).name.to_string(),
+                _ => panic!("expected a method, got {:?}", item),
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    assert_eq!(method_names(None), vec!["base", "count", "next"]);
+    assert_eq!(method_names(Some(&name("base"))), vec!["base"]);
+    assert!(dyn_trait_methods(&ctx.db, &Ty::simple(TypeCtor::Bool), None).is_empty());
+}
+
+#[test]
+fn lookup_method_canonical_receiver() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn len(&self) -> usize { 0 }
+}
+fn test(v: &Vec<i32>) { v<|>; }
+"#,
+    );
+    let lookup = |ty: &Canonical<Ty>| {
+        lookup_method_canonical(
+            ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name("len"),
+        )
+        .unwrap()
+        .0
+    };
+
+    let receiver = lookup(&ctx.ty);
+    assert_eq!(receiver.num_vars, 0);
+    assert_eq!(receiver.value.display(&ctx.db).to_string(), "Vec<i32>");
+
+    // `Vec<?3>` from a caller with four variables comes back as `Vec<?0>`.
+    let vec_ctor = match ctx.ty.value.as_reference().unwrap().0 {
+        Ty::Apply(a_ty) => a_ty.ctor,
+        ty => panic!("expected `Vec<i32>`, got {:?}", ty),
+    };
+    let vec_of_var =
+        |index| Ty::apply_one(vec_ctor, Ty::Bound(BoundVar::new(DebruijnIndex::INNERMOST, index)));
+    let receiver = lookup(&Canonical { value: vec_of_var(3), num_vars: 4 });
+    assert_eq!(receiver.num_vars, 1);
+    assert_eq!(receiver.value, vec_of_var(0));
+}
+
+#[test]
+fn fingerprint_for_method_lookup_of_struct_reference_and_unknown() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+fn test(s: &S) { s<|>; }
+"#,
+    );
+    let (struct_ty, _) = ctx.ty.value.as_reference().unwrap();
+    let fingerprint = fingerprint_for_method_lookup(struct_ty).unwrap();
+    assert!(matches!(fingerprint, TyFingerprint::Apply(TypeCtor::Adt(_))));
+    assert!(fingerprint.to_string().starts_with("Apply(Adt("));
+    assert_eq!(
+        fingerprint_for_method_lookup(&ctx.ty.value),
+        Some(TyFingerprint::Apply(TypeCtor::Ref(Mutability::Shared)))
+    );
+    assert_eq!(fingerprint_for_method_lookup(&Ty::Unknown), None);
+}
+
+#[test]
+fn unique_method_names_are_reported_once() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn inherent(&self) {}
+}
+trait A {
+    fn foo(&self) {}
+}
+trait B {
+    fn foo(&self) {}
+}
+impl A for S {}
+impl B for S {}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let mut names = Vec::new();
+    iterate_unique_method_names(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        |name, _item| names.push(name.to_string()),
+    );
+    names.sort();
+    assert_eq!(names, vec!["foo", "inherent"]);
+}
+
+#[test]
+fn method_candidates_with_control_stops_on_request() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn a(&self) {}
+    fn b(&self) {}
+    fn c(&self) {}
+    fn d(&self) {}
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let mut visited = Vec::new();
+    let res = iterate_method_candidates_with_control(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        None,
+        LookupMode::MethodCall,
+        |_ty, item| {
+            visited.push(item);
+            if visited.len() == 2 {
+                ControlFlow::Stop(visited.clone())
+            } else {
+                ControlFlow::Continue
+            }
+        },
+    );
+    assert_eq!(visited.len(), 2);
+    assert_eq!(res, Some(visited));
+}
+
+#[test]
+fn lookup_qualified_method_restricts_to_trait() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+mod iter {
+    pub trait Iterator {
+        type Item;
+        fn next(&mut self) -> Self::Item;
+        fn by_ref(&mut self) {}
+    }
+}
+struct S;
+impl S {
+    fn next(&mut self) {}
+    fn method() {}
+}
+impl iter::Iterator for S {
+    type Item = u32;
+    fn next(&mut self) -> u32 { 0 }
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let iterator = ctx.db.impls_in_crate(ctx.krate).traits().next().unwrap();
+    let lookup = |trait_, text| {
+        lookup_qualified_method(&ctx.db, &ctx.ty, trait_, &name(text), ctx.env.clone(), ctx.krate)
+            .map(|(_ty, func)| func.lookup(&ctx.db).container)
+    };
+
+    // `<S as Iterator>::next(...)`
+    assert_eq!(lookup(Some(iterator), "next"), Some(AssocContainerId::TraitId(iterator)));
+    assert_eq!(lookup(Some(iterator), "method"), None);
+
+    // `S::method(...)`
+    assert!(matches!(lookup(None, "method"), Some(AssocContainerId::ImplId(_))));
+    assert!(matches!(lookup(None, "next"), Some(AssocContainerId::ImplId(_))));
+    assert_eq!(lookup(None, "by_ref"), Some(AssocContainerId::TraitId(iterator)));
+}
+
+#[test]
+fn has_inherent_methods_checks_for_functions() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn method(&self) {}
+}
+struct Bare;
+impl Bare {
+    const C: u32 = 0;
+}
+fn test(s: S, b: Bare) { (s, b)<|>; }
+"#,
+    );
+    let (s, bare) = match &ctx.ty.value {
+        Ty::Apply(tuple) => (tuple.parameters[0].clone(), tuple.parameters[1].clone()),
+        ty => panic!("expected a tuple, got {:?}", ty),
+    };
+    assert!(has_inherent_methods(&ctx.db, &s, ctx.krate));
+    assert!(!has_inherent_methods(&ctx.db, &bare, ctx.krate));
+}
+
+#[test]
+fn shadowing_method_reports_inherent_and_trait_method() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn foo(&self) {}
+    fn bar(&self) {}
+}
+trait Tr {
+    fn foo(&self);
+}
+impl Tr for S {
+    fn foo(&self) {}
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let shadowing = |text| {
+        shadowing_method(
+            &ctx.db,
+            &ctx.ty,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name(text),
+        )
+    };
+
+    let (inherent, trait_method) = shadowing("foo").unwrap();
+    assert!(matches!(inherent.lookup(&ctx.db).container, AssocContainerId::ImplId(_)));
+    assert!(matches!(trait_method.lookup(&ctx.db).container, AssocContainerId::TraitId(_)));
+    assert_eq!(ctx.db.function_data(inherent).name.to_string(), "foo");
+    assert_eq!(ctx.db.function_data(trait_method).name.to_string(), "foo");
+
+    assert_eq!(shadowing("bar"), None);
+}
+
+#[test]
+fn lookup_method_reports_deref_count() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn foo(&self) {}
+}
+fn test(s: &&S) { s<|>; }
+"#,
+    );
+    let (self_ty, deref_count, autoref, func) = lookup_method_with_deref_count(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        &name("foo"),
+    )
+    .unwrap();
+    assert_eq!(self_ty.display(&ctx.db).to_string(), "S");
+    assert_eq!(deref_count, 1);
+    assert_eq!(autoref, None::<Mutability>);
+    assert_eq!(ctx.db.function_data(func).name.to_string(), "foo");
+}
+
+#[test]
+fn trait_items_include_supertraits() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+trait Base {
+    fn base(&self);
+    fn shared(&self);
+}
+trait Derived: Base {
+    fn derived(&self);
+    fn shared(&self);
+}
+struct S;
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let trait_named = |text: &str| {
+        *ctx.traits_in_scope
+            .iter()
+            .find(|&&t| ctx.db.trait_data(t).name.to_string() == text)
+            .unwrap()
+    };
+    let derived = trait_named("Derived");
+
+    let items = trait_items_including_supertraits(&ctx.db, derived);
+    let mut names = items.iter().map(|(name, _)| name.to_string()).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["base", "derived", "shared"]);
+
+    let (_, shared) = items.iter().find(|(name, _)| name.to_string() == "shared").unwrap();
+    let container = match shared {
+        AssocItemId::FunctionId(func) => func.lookup(&ctx.db).container,
+        _ => panic!("expected a method, got {:?}", shared),
+    };
+    assert_eq!(container, AssocContainerId::TraitId(derived));
+}
+
+#[test]
+fn pattern_lookup_skips_functions() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    #[allow(non_snake_case)]
+    fn FOO() -> S { S }
+}
+trait Tr {
+    const FOO: S;
+}
+impl Tr for S {
+    const FOO: S = S;
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let lookup = |mode| {
+        iterate_method_candidates(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            Some(&name("FOO")),
+            mode,
+            |_ty, item| Some(item),
+        )
+        .unwrap()
+    };
+    assert!(matches!(lookup(LookupMode::Path), AssocItemId::FunctionId(_)));
+    match lookup(LookupMode::Pattern) {
+        AssocItemId::ConstId(c) => assert_eq!(ctx.db.const_data(c).name, Some(name("FOO"))),
+        item => panic!("expected a const, got {:?}", item),
+    }
+}
+
+#[test]
+fn ranked_method_candidates_prefer_inherent_and_direct() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn on_ref(&self) {}
+    fn by_value(self) {}
+}
+trait Tr {
+    fn from_trait(&self);
+}
+impl Tr for S {
+    fn from_trait(&self) {}
+}
+fn test(s: &S) { s<|>; }
+"#,
+    );
+    let ranked = ranked_method_candidates(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        None,
+    );
+    let relevance = |text: &str| -> Relevance {
+        ranked
+            .iter()
+            .find_map(|&(item, relevance)| match item {
+                AssocItemId::FunctionId(f) if ctx.db.function_data(f).name == name(text) => {
+                    Some(relevance)
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    assert_eq!(
+        relevance("on_ref"),
+        Relevance { from_trait: false, deref_count: 0, autoref: false }
+    );
+    assert_eq!(
+        relevance("by_value"),
+        Relevance { from_trait: false, deref_count: 1, autoref: false }
+    );
+    assert!(relevance("on_ref") < relevance("from_trait"));
+    assert!(relevance("on_ref") < relevance("by_value"));
+    assert!(ranked.windows(2).all(|w| w[0].1 <= w[1].1));
+}
+
+#[test]
+fn trace_method_resolution_records_steps() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn foo(&self) {}
+}
+fn test(s: &S) { s<|>; }
+"#,
+    );
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    let func = trace_method_resolution(
+        {
+            let steps = steps.clone();
+            move |step| steps.borrow_mut().push(step)
+        },
+        || {
+            iterate_method_candidates(
+                &ctx.ty,
+                &ctx.db,
+                ctx.env.clone(),
+                ctx.krate,
+                &ctx.traits_in_scope,
+                Some(&name("foo")),
+                LookupMode::MethodCall,
+                |_ty, item| Some(item),
+            )
+            .unwrap()
+        },
+    );
+
+    let steps = steps.borrow();
+    assert_eq!(steps[0], MethodResolutionStep::Deref { deref_count: 0, ty: ctx.ty.value.clone() });
+    assert_eq!(steps.last(), Some(&MethodResolutionStep::CandidateAccepted(func)));
+}
+
+#[test]
+fn trace_method_resolution_from_the_sink() {
+    let steps = Rc::new(RefCell::new(Vec::new()));
+    trace_method_resolution(
+        {
+            let steps = steps.clone();
+            move |step| {
+                // Not reported, the sink is busy.
+                trace(|| MethodResolutionStep::Autoref(Some(Mutability::Mut)));
+                steps.borrow_mut().push(step)
+            }
+        },
+        || {
+            trace(|| MethodResolutionStep::Autoref(None));
+            trace(|| MethodResolutionStep::Autoref(Some(Mutability::Shared)));
+        },
+    );
+
+    assert_eq!(
+        *steps.borrow(),
+        vec![
+            MethodResolutionStep::Autoref(None),
+            MethodResolutionStep::Autoref(Some(Mutability::Shared))
+        ]
+    );
+}
+
+#[test]
+fn trait_only_method_candidates_mark_imports() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn inherent(&self) {}
+}
+trait InScope {
+    fn in_scope(&self);
+}
+impl InScope for S {
+    fn in_scope(&self) {}
+}
+mod m {
+    pub trait OutOfScope {
+        fn out_of_scope(&self);
+    }
+    impl OutOfScope for super::S {
+        fn out_of_scope(&self) {}
+    }
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let candidates = trait_only_method_candidates(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        None,
+    );
+    let mut names = candidates
+        .iter()
+        .map(|&TraitMethodCandidate { item, needs_import, .. }| match item {
+            AssocItemId::FunctionId(f) => (ctx.db.function_data(f).name.to_string(), needs_import),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec![("in_scope".to_string(), false), ("out_of_scope".to_string(), true)]);
+}
+
+#[test]
+fn diagnose_method_lookup_outcomes() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn found(&self) {}
+}
+mod m {
+    pub trait Hidden {
+        fn hidden(&self);
+    }
+    impl Hidden for super::S {
+        fn hidden(&self) {}
+    }
+}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let diagnose = |text: &str| {
+        diagnose_method_lookup(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name(text),
+        )
+    };
+
+    match diagnose("found") {
+        MethodLookupDiagnosis::Found(func) => {
+            assert_eq!(ctx.db.function_data(func).name, name("found"))
+        }
+        it => panic!("unexpected diagnosis: {:?}", it),
+    }
+    match diagnose("hidden") {
+        MethodLookupDiagnosis::NeedsTraitImport(traits) => {
+            assert_eq!(traits.len(), 1);
+            assert_eq!(ctx.db.trait_data(traits[0]).name, name("Hidden"));
+        }
+        it => panic!("unexpected diagnosis: {:?}", it),
+    }
+    assert_eq!(diagnose("missing"), MethodLookupDiagnosis::NotFound);
+}
+
+#[test]
+fn impl_items_by_name_matches_scanning() {
+    test_utils::mark::check!(inherent_impl_items_by_name);
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn foo(&self) {}
+    fn bar() {}
+    const BAZ: u32 = 0;
+    type Foo = u32;
+}
+#[allow(non_upper_case_globals)]
+impl S {
+    const foo: u32 = 1;
+    const _: () = ();
+}
+trait Tr {
+    fn foo(&self) {}
+    fn quux() {}
+}
+impl Tr for S {}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let candidates = |lookup: Option<&Name>| {
+        let mut res = Vec::new();
+        iterate_method_candidates(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            lookup,
+            LookupMode::Path,
+            |_ty, item| {
+                res.push(item);
+                None::<()>
+            },
+        );
+        res
+    };
+    let item_name = |item: AssocItemId| match item {
+        AssocItemId::FunctionId(f) => Some(ctx.db.function_data(f).name.clone()),
+        AssocItemId::ConstId(c) => ctx.db.const_data(c).name.clone(),
+        AssocItemId::TypeAliasId(_) => None,
+    };
+
+    let all = candidates(None);
+    for text in &["foo", "bar", "BAZ", "Foo", "quux", "missing"] {
+        let scanned = all
+            .iter()
+            .copied()
+            .filter(|&item| item_name(item) == Some(name(text)))
+            .collect::<Vec<_>>();
+        assert_eq!(candidates(Some(&name(text))), scanned, "lookup of `{}`", text);
+    }
+    assert_eq!(candidates(Some(&name("foo"))).len(), 3);
+}
+
+#[test]
+fn method_receiver_type_substitutes_impl_generics() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct Foo<T>(T);
+impl<T> Foo<T> {
+    fn get(&mut self) -> &mut T { loop {} }
+    fn new(t: T) -> Foo<T> { Foo(t) }
+}
+fn test(foo: Foo<i32>) { foo<|>; }
+"#,
+    );
+    let func = |text: &str| match lookup_path_assoc_item(
+        &ctx.ty,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        &name(text),
+    ) {
+        Some((_, AssocItemId::FunctionId(func))) => func,
+        it => panic!("expected a function, got {:?}", it),
+    };
+
+    let receiver = method_receiver_type(&ctx.db, func("get"), &ctx.ty).unwrap();
+    assert_eq!(receiver.display(&ctx.db).to_string(), "&mut Foo<i32>");
+    assert_eq!(method_receiver_type(&ctx.db, func("new"), &ctx.ty), None);
+}
+
+#[test]
+fn lookup_method_without_trait_tells_needed_imports() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn inherent(&self) {}
+}
+mod m {
+    pub trait Tr {
+        fn provided(&self);
+        fn inherent(&self);
+    }
+    impl Tr for super::S {
+        fn provided(&self) {}
+        fn inherent(&self) {}
+    }
+}
+use m::Tr;
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let tr =
+        *ctx.traits_in_scope.iter().find(|&&t| ctx.db.trait_data(t).name == name("Tr")).unwrap();
+    let lookup = |text: &str| {
+        lookup_method_without_trait(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name(text),
+            tr,
+        )
+    };
+
+    // `provided` only resolves through `Tr`, so the import is needed
+    assert!(lookup_method_for_ty(
+        &ctx.ty.value,
+        &ctx.db,
+        ctx.env.clone(),
+        ctx.krate,
+        &ctx.traits_in_scope,
+        &name("provided"),
+    )
+    .is_some());
+    assert_eq!(lookup("provided"), None);
+
+    // `inherent` still resolves to the inherent method, so the import is redundant
+    let (_, func) = lookup("inherent").unwrap();
+    assert!(matches!(func.lookup(&ctx.db).container, AssocContainerId::ImplId(_)));
+}
+
+#[test]
+fn deref_targets_follow_deref_impls() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+struct Outer;
+struct Wrapper<T>(T);
+struct Inner;
+impl Deref for Outer {
+    type Target = Wrapper<Inner>;
+}
+impl<T> Deref for Wrapper<T> {
+    type Target = T;
+}
+fn test(o: &Outer) { o<|>; }
+"#,
+    );
+    let targets = deref_targets(&ctx.db, &ctx.ty.value, ctx.env.clone(), ctx.krate)
+        .into_iter()
+        .map(|(ty, kind)| (ty.display(&ctx.db).to_string(), kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        targets,
+        vec![
+            ("Outer".to_string(), DerefKind::Builtin),
+            ("Wrapper<Inner>".to_string(), DerefKind::Trait),
+            ("Inner".to_string(), DerefKind::Trait),
+        ]
+    );
+}
+
+#[test]
+fn lookup_inherent_method_with_impl_returns_defining_impl() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+struct S;
+impl S {
+    fn first(&self) {}
+}
+impl S {
+    fn second(&self) {}
+}
+trait Tr {
+    fn from_trait(&self) {}
+}
+impl Tr for S {}
+fn test(s: S) { s<|>; }
+"#,
+    );
+    let lookup = |text: &str| {
+        lookup_inherent_method_with_impl(
+            &ctx.ty,
+            &ctx.db,
+            ctx.env.clone(),
+            ctx.krate,
+            &ctx.traits_in_scope,
+            &name(text),
+        )
+    };
+
+    let (_, impl_id, func) = lookup("second").unwrap();
+    assert!(ctx.db.impl_data(impl_id).items.contains(&AssocItemId::FunctionId(func)));
+    let (_, first_impl, _) = lookup("first").unwrap();
+    assert_ne!(first_impl, impl_id);
+    assert_eq!(lookup("from_trait"), None);
+}
+
+#[test]
+fn operator_method_resolves_add() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+#[lang = "add"]
+pub trait Add<Rhs = Self> {
+    type Output;
+    fn add(self, rhs: Rhs) -> Self::Output;
+}
+#[lang = "sub"]
+pub trait Sub<Rhs = Self> {
+    type Output;
+    fn sub(self, rhs: Rhs) -> Self::Output;
+}
+
+struct V;
+impl Add for V {
+    type Output = V;
+    fn add(self, rhs: V) -> V { rhs }
+}
+
+fn test(v: V) { v<|> + V; }
+"#,
+    );
+    let resolve =
+        |op| operator_method(&ctx.db, &ctx.ty, ctx.env.clone(), ctx.krate, Operator::from(op));
+
+    let (trait_, func) = resolve(BinaryOp::ArithOp(ArithOp::Add)).unwrap();
+    assert_eq!(ctx.db.trait_data(trait_).name.to_string(), "Add");
+    assert_eq!(ctx.db.function_data(func).name.to_string(), "add");
+    assert_eq!(resolve(BinaryOp::ArithOp(ArithOp::Sub)), None);
+    assert_eq!(resolve(BinaryOp::LogicOp(LogicOp::And)), None);
+}
+
+#[test]
+fn operator_method_does_not_autoderef_binary_operands() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+#[lang = "add"]
+pub trait Add<Rhs = Self> {
+    type Output;
+    fn add(self, rhs: Rhs) -> Self::Output;
+}
+
+struct V;
+impl Add for V {
+    type Output = V;
+    fn add(self, rhs: V) -> V { rhs }
+}
+
+fn test(v: &V) { v<|>; }
+"#,
+    );
+    let add = Operator::from(BinaryOp::ArithOp(ArithOp::Add));
+    assert_eq!(operator_method(&ctx.db, &ctx.ty, ctx.env.clone(), ctx.krate, add), None);
+}
+
+#[test]
+fn operator_method_resolves_index_through_deref() {
+    let ctx = MethodResolutionCtx::new(
+        r#"
+//- /main.rs
+#[lang = "index"]
+pub trait Index<Idx> {
+    type Output;
+    fn index(&self, index: Idx) -> &Self::Output;
+}
+
+struct Grid;
+impl Index<usize> for Grid {
+    type Output = u8;
+    fn index(&self, index: usize) -> &u8 { loop {} }
+}
+
+fn test(g: &Grid) { g<|>[0]; }
+"#,
+    );
+
+    let (trait_, func) =
+        operator_method(&ctx.db, &ctx.ty, ctx.env.clone(), ctx.krate, Operator::Index).unwrap();
+    assert_eq!(ctx.db.trait_data(trait_).name.to_string(), "Index");
+    assert_eq!(ctx.db.function_data(func).name.to_string(), "index");
+}
+
+#[test]
+fn impls_from_deps_skip_proc_macro_deps() {
+    let db = TestDB::with_files(
+        r#"
+//- /main.rs crate:main deps:pm(proc-macro)
+//- /plain.rs crate:plain deps:pm
+//- /pm.rs crate:pm deps:helper
+pub struct P;
+impl P {}
+//- /helper.rs crate:helper
+pub trait T {}
+pub struct H;
+impl T for H {}
+"#,
+    );
+    let crate_graph = db.crate_graph();
+    let krate = |name: &str| {
+        crate_graph
+            .iter()
+            .find(|&krate| crate_graph[krate].display_name.as_ref().unwrap().to_string() == name)
+            .unwrap()
+    };
+
+    assert_eq!(db.impls_from_deps(krate("main")).all_impls().count(), 0);
+    assert_eq!(db.impls_from_deps(krate("plain")).all_impls().count(), 2);
+}
+
+fn impl_id(id: u32) -> ImplId {
+    ImplId::from_intern_id(InternId::from(id))
+}
+
+#[test]
+fn crate_impl_defs_from_raw_falls_back_to_blanket_impls() {
+    let trait_ = TraitId::from_intern_id(InternId::from(0u32));
+    let other_trait = TraitId::from_intern_id(InternId::from(1u32));
+    let bool_fp = TyFingerprint::Apply(TypeCtor::Bool);
+    let char_fp = TyFingerprint::Apply(TypeCtor::Char);
+    let impls = CrateImplDefs::from_raw(
+        vec![(bool_fp, impl_id(0))],
+        vec![
+            (trait_, Some(bool_fp), impl_id(1)),
+            (trait_, None, impl_id(2)),
+            (trait_, Some(char_fp), impl_id(3)),
+        ],
+    );
+
+    let for_trait_and_ty =
+        |tr, fp| impls.lookup_impl_defs_for_trait_and_ty(tr, fp).collect::<Vec<_>>();
+    assert_eq!(for_trait_and_ty(trait_, bool_fp), vec![impl_id(1), impl_id(2)]);
+    assert_eq!(for_trait_and_ty(trait_, TyFingerprint::Apply(TypeCtor::Str)), vec![impl_id(2)]);
+    assert_eq!(for_trait_and_ty(other_trait, bool_fp), vec![]);
+
+    let mut for_trait = impls.lookup_impl_defs_for_trait(trait_).collect::<Vec<_>>();
+    for_trait.sort();
+    assert_eq!(for_trait, vec![impl_id(1), impl_id(2), impl_id(3)]);
+
+    let inherent = impls.lookup_impl_defs(&Ty::simple(TypeCtor::Bool)).collect::<Vec<_>>();
+    assert_eq!(inherent, vec![impl_id(0)]);
+    assert_eq!(impls.all_impls().count(), 4);
+}
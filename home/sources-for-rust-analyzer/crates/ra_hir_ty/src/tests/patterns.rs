@@ -552,6 +552,46 @@ fn test() {
     );
 }
 
+#[test]
+fn infer_variant_and_assoc_const_paths_in_patterns() {
+    assert_snapshot!(
+        infer(r#"
+enum E { A, B }
+type Alias = E;
+struct S;
+impl S {
+    const C: S = S;
+}
+
+fn test(e: E, s: S) {
+    match e {
+        Alias::A => {}
+        Alias::B => {}
+    };
+    match s {
+        S::C => {}
+    };
+}
+"#),
+        @r###"
+    68..69 'S': S
+    82..83 'e': E
+    88..89 's': S
+    94..204 '{     ...  }; }': ()
+    100..161 'match ...     }': ()
+    106..107 'e': E
+    118..126 'Alias::A': E
+    130..132 '{}': ()
+    141..149 'Alias::B': E
+    153..155 '{}': ()
+    167..201 'match ...     }': ()
+    173..174 's': S
+    185..189 'S::C': S
+    193..195 '{}': ()
+    "###
+    );
+}
+
 #[test]
 fn infer_guard() {
     assert_snapshot!(
@@ -1703,6 +1703,25 @@ fn main() {
     assert_eq!(t, "Foo");
 }
 
+#[test]
+fn infer_let_else_branch() {
+    let t = type_at(
+        r#"
+//- /main.rs
+enum Option<T> { None, Some(T) }
+use Option::*;
+
+fn test(opt: Option<u32>) {
+    let Some(x) = opt else {
+        let y = 1u8;
+        y<|>;
+        return;
+    };
+}"#,
+    );
+    assert_eq!(t, "u8");
+}
+
 #[test]
 fn not_shadowing_primitive_by_module() {
     let t = type_at(
@@ -1644,6 +1644,45 @@ fn test<T: A>(x: T) {
     );
 }
 
+#[test]
+fn super_trait_cycle_method() {
+    mark::check!(supertrait_cycle);
+    let t = type_at(
+        r#"
+//- /main.rs
+trait A: B {
+    fn a(&self) -> u32;
+}
+trait B: A {}
+
+fn test<T: B>(x: T) {
+    x.a()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn super_trait_cycle_not_through_root() {
+    mark::check!(supertrait_cycle);
+    let t = type_at(
+        r#"
+//- /main.rs
+trait A: B {}
+trait B: C {}
+trait C: B {
+    fn c(&self) -> u32;
+}
+
+fn test<T: A>(x: T) {
+    x.c()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
 #[test]
 fn super_trait_assoc_type_bounds() {
     assert_snapshot!(
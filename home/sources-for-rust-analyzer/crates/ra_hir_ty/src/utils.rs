@@ -13,6 +13,8 @@ use hir_def::{
     AssocContainerId, GenericDefId, Lookup, TraitId, TypeAliasId, TypeParamId, VariantId,
 };
 use hir_expand::name::{name, Name};
+use rustc_hash::FxHashSet;
+use test_utils::mark;
 
 use crate::{db::HirDatabase, GenericPredicate, TraitRef};
 
@@ -71,14 +73,16 @@ pub(super) fn all_super_traits(db: &dyn DefDatabase, trait_: TraitId) -> Vec<Tra
     // we need to take care a bit here to avoid infinite loops in case of cycles
     // (i.e. if we have `trait A: B; trait B: A;`)
     let mut result = vec![trait_];
+    let mut visited: FxHashSet<TraitId> = result.iter().copied().collect();
     let mut i = 0;
     while i < result.len() {
         let t = result[i];
-        // yeah this is quadratic, but trait hierarchies should be flat
-        // enough that this doesn't matter
         for tt in direct_super_traits(db, t) {
-            if !result.contains(&tt) {
+            if visited.insert(tt) {
                 result.push(tt);
+            } else if has_super_trait(db, tt, t) {
+                // `tt` is visited again, and `t` is one of its super traits
+                mark::hit!(supertrait_cycle);
             }
         }
         i += 1;
@@ -86,6 +90,21 @@ pub(super) fn all_super_traits(db: &dyn DefDatabase, trait_: TraitId) -> Vec<Tra
     result
 }
 
+/// Whether `super_trait` is `trait_` itself or one of its (transitive) super traits.
+fn has_super_trait(db: &dyn DefDatabase, trait_: TraitId, super_trait: TraitId) -> bool {
+    let mut stack = vec![trait_];
+    let mut visited = FxHashSet::default();
+    while let Some(t) = stack.pop() {
+        if t == super_trait {
+            return true;
+        }
+        if visited.insert(t) {
+            stack.extend(direct_super_traits(db, t));
+        }
+    }
+    false
+}
+
 /// Given a trait ref (`Self: Trait`), builds all the implied trait refs for
 /// super traits. The original trait ref will be included. So the difference to
 /// `all_super_traits` is that we keep track of type parameters; for example if
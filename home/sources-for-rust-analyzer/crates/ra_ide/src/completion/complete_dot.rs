@@ -1,6 +1,7 @@
 //! FIXME: write short doc here
 
 use hir::{HasVisibility, Type};
+use ra_syntax::ast;
 
 use crate::{
     completion::{
@@ -18,7 +19,11 @@ pub(super) fn complete_dot(acc: &mut Completions, ctx: &CompletionContext) {
         _ => return,
     };
 
-    let receiver_ty = match ctx.sema.type_of_expr(&dot_receiver) {
+    let builtin_index = match dot_receiver {
+        ast::Expr::IndexExpr(index) => ctx.sema.type_of_builtin_index(index),
+        _ => None,
+    };
+    let receiver_ty = match builtin_index.or_else(|| ctx.sema.type_of_expr(&dot_receiver)) {
         Some(ty) => ty,
         _ => return,
     };
@@ -105,6 +110,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builtin_index_field_completion() {
+        assert_debug_snapshot!(
+        do_ref_completion(
+                r"
+                struct A { the_field: u32 }
+                fn foo(a: &[A]) {
+                a[0].<|>
+                }
+                ",
+        ),
+            @r###"
+        [
+            CompletionItem {
+                label: "the_field",
+                source_range: 51..51,
+                delete: 51..51,
+                insert: "the_field",
+                kind: Field,
+                detail: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn test_struct_field_completion_self() {
         assert_debug_snapshot!(
@@ -17,7 +17,12 @@ pub(super) fn complete_macro_in_item_position(acc: &mut Completions, ctx: &Compl
 mod tests {
     use insta::assert_debug_snapshot;
 
-    use crate::completion::{test_utils::do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{
+        completion_context::CompletionContext, test_utils::do_completion, CompletionItem,
+        CompletionKind, Completions,
+    };
+
+    use super::complete_macro_in_item_position;
 
     fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Reference)
@@ -141,4 +146,29 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn completes_macros_from_a_test_context() {
+        let items: Vec<CompletionItem> = CompletionContext::with_test_ctx(
+            r"
+            macro_rules! foo {
+                () => {}
+            }
+            macro_rules! bar {
+                () => {}
+            }
+
+            <|>
+            ",
+            |ctx| {
+                let mut acc = Completions::default();
+                complete_macro_in_item_position(&mut acc, ctx);
+                acc
+            },
+        )
+        .into();
+        let mut labels: Vec<&str> = items.iter().map(|it| it.label()).collect();
+        labels.sort();
+        assert_eq!(labels, vec!["bar!(…)", "foo!(…)"]);
+    }
 }
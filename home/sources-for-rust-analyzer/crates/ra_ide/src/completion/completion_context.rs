@@ -193,6 +193,20 @@ impl<'a> CompletionContext<'a> {
         Some(ctx)
     }
 
+    /// Builds a context positioned at the `<|>` marker of `ra_fixture` and
+    /// passes it to `f`, so that a single completion handler can be exercised
+    /// in isolation.
+    #[cfg(test)]
+    pub(super) fn with_test_ctx<T>(ra_fixture: &str, f: impl FnOnce(&CompletionContext) -> T) -> T {
+        let (mock, position) =
+            crate::mock_analysis::MockAnalysis::with_files_and_position(ra_fixture);
+        let host = mock.analysis_host();
+        let config = CompletionConfig::default();
+        let ctx = CompletionContext::new(host.raw_database(), position, &config)
+            .expect("failed to build a completion context at the marker");
+        f(&ctx)
+    }
+
     // The range of the identifier that is being completed.
     pub(crate) fn source_range(&self) -> TextRange {
         // check kind of macro-expanded token, but use range of original token
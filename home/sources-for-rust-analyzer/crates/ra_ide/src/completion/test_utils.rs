@@ -1,7 +1,8 @@
 //! Runs completion for testing purposes.
 
 use hir::Semantics;
-use ra_syntax::{AstNode, NodeOrToken, SyntaxElement};
+use ra_syntax::{AstNode, NodeOrToken, SyntaxElement, TextRange};
+use test_utils::assert_panics_with;
 
 use crate::{
     completion::{completion_item::CompletionKind, CompletionConfig},
@@ -46,6 +47,44 @@ pub(crate) fn completion_list_with_options(
         .collect()
 }
 
+/// Asserts that the item labeled `before` precedes the one labeled `after` in
+/// `items`, e.g. to check relevance ranking without snapshotting everything.
+pub(crate) fn assert_completion_order(items: &[CompletionItem], before: &str, after: &str) {
+    let position = |label: &str| {
+        items.iter().position(|it| it.label() == label).unwrap_or_else(|| {
+            let labels = items.iter().map(|it| it.label()).collect::<Vec<_>>();
+            panic!("no completion labeled `{}` among {:?}", label, labels)
+        })
+    };
+    let (before_idx, after_idx) = (position(before), position(after));
+    assert!(
+        before_idx < after_idx,
+        "expected `{}` (at {}) before `{}` (at {})",
+        before,
+        before_idx,
+        after,
+        after_idx
+    );
+}
+
+#[test]
+fn assert_completion_order_compares_positions() {
+    let items = ["inherent_method", "trait_method"]
+        .iter()
+        .map(|&label| {
+            CompletionItem::new(CompletionKind::Reference, TextRange::empty(0.into()), label)
+                .build()
+        })
+        .collect::<Vec<_>>();
+    assert_completion_order(&items, "inherent_method", "trait_method");
+    assert_panics_with("expected `trait_method` (at 1) before `inherent_method` (at 0)", || {
+        assert_completion_order(&items, "trait_method", "inherent_method")
+    });
+    assert_panics_with("no completion labeled `missing`[..]", || {
+        assert_completion_order(&items, "missing", "trait_method")
+    });
+}
+
 pub(crate) fn check_pattern_is_applicable(code: &str, check: fn(SyntaxElement) -> bool) {
     let (analysis, pos) = analysis_and_position(code);
     analysis
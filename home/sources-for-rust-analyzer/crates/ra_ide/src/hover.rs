@@ -169,7 +169,11 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
                 return None;
             },
             ast::Expr(it) => {
-                sema.type_of_expr(&it)
+                let builtin_index = match &it {
+                    ast::Expr::IndexExpr(index) => sema.type_of_builtin_index(index),
+                    _ => None,
+                };
+                builtin_index.or_else(|| sema.type_of_expr(&it))
             },
             ast::Pat(it) => {
                 sema.type_of_pat(&it)
@@ -456,6 +460,28 @@ fn main() {
         assert_eq!(trim_markup_opt(hover.info.first()), Some("u32"));
     }
 
+    #[test]
+    fn hover_shows_type_of_builtin_indexing() {
+        check_hover_result(
+            r#"
+//- /main.rs
+fn main(xs: &[u32], s: &str) {
+    xs[0]<|>;
+}
+"#,
+            &["u32"],
+        );
+        check_hover_result(
+            r#"
+//- /main.rs
+fn main(xs: &[u32], s: &str) {
+    s[..]<|>;
+}
+"#,
+            &["str"],
+        );
+    }
+
     #[test]
     fn hover_shows_long_type_of_an_expression() {
         check_hover_result(
@@ -1,25 +1,219 @@
-use hir::{Adt, HirDisplay, Semantics, Type};
-use ra_ide_db::RootDatabase;
+use either::Either;
+use hir::{
+    Access, Adt, AsAssocItem, AssocItemContainer, HirDisplay, Local, MutabilityStyle,
+    PathResolution, Semantics, StructKind, Trait, Type, VariantDef,
+};
+use ra_ide_db::{line_index::LineIndex, LineIndexDatabase, RootDatabase};
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, ArgListOwner, AstNode, TypeAscriptionOwner},
-    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, TextRange,
+    algo::find_node_at_offset,
+    ast::{self, ArgListOwner, AstNode, AttrsOwner, TypeAscriptionOwner},
+    match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, SyntaxNode, TextRange, WalkEvent,
 };
 
-use crate::{FileId, FunctionSignature};
+use crate::{FileId, FilePosition, FunctionSignature};
 use stdx::to_lower_snake_case;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct InlayHintsConfig {
     pub type_hints: bool,
+    /// Which type hints of bindings are shown.
+    pub type_hint_verbosity: HintVerbosity,
     pub parameter_hints: bool,
     pub chaining_hints: bool,
     pub max_length: Option<usize>,
+    /// Generic arguments nested deeper than this are shown as `…`.
+    pub max_type_depth: Option<usize>,
+    /// Merges parameter hints of adjacent arguments on the same line into a
+    /// single `x, y, z` hint.
+    pub combine_consecutive_param_hints: bool,
+    /// Merges a parameter hint and a type hint with the same range into a
+    /// single `name: type` hint.
+    pub merge_overlapping_hints: bool,
+    /// Shows the error type propagated by `?` on the `?` token.
+    pub question_mark_hints: bool,
+    /// Shows the output type of awaited futures on the `.await` tokens.
+    pub await_hints: bool,
+    /// Shows the field type of `.0`-style accesses on tuple structs.
+    pub tuple_field_hints: bool,
+    /// Shows the type of unsuffixed number literals inferred as something
+    /// other than the `i32`/`f64` fallback, as in `let x: u8 = 5;`.
+    pub literal_width_hints: bool,
+    /// Shows the type of the `self` parameter of methods on the `self` token.
+    pub self_type_hint: bool,
+    /// Summarizes the locals captured by a closure, as in `move: x; ref: y`,
+    /// on its parameter list.
+    pub closure_capture_hints: bool,
+    /// Type names (without generic arguments) never shown as type hints.
+    pub type_hint_denylist: Vec<SmolStr>,
+    /// Suppresses type hints for bindings of the unit type `()`.
+    pub hide_unit_type_hints: bool,
+    /// Appends the size of the type to type hints, where it is known.
+    pub layout_hints: bool,
+    /// Appends the enum variant matched by the enclosing pattern to type
+    /// hints of bindings, as in `i32 (Some)` for `Some(x)`.
+    pub pattern_variant_hints: bool,
+    /// Prefixes type hints of `ref`/`ref mut` bindings with their binding
+    /// mode, as in `ref mut &mut T`.
+    pub explicit_ref_binding_hints: bool,
+    /// Appends `(via Trait)` to chaining hints of method calls resolved
+    /// through a trait.
+    pub chaining_hints_show_trait: bool,
+    /// Shows parameter hints for `true`/`false` literal arguments even where
+    /// the usual heuristics would hide them.
+    pub always_hint_bool_literals: bool,
+    /// How `&mut` is spelled in type labels.
+    pub mutability_style: MutabilityStyle,
+    /// Separates generic arguments in type labels, as in `HashMap<K, V>`.
+    pub type_arg_separator: SmolStr,
+    /// How `Box`, `Rc` and `Arc` are shown in type labels.
+    pub smart_pointer_style: SmartPtrStyle,
+    /// Shows projections like `<S as Trait>::Assoc` in type labels as the
+    /// type they normalize to, where that is known.
+    pub normalize_projection_hints: bool,
+    /// Keeps only the leftmost this many hints of each line.
+    pub max_hints_per_line: Option<usize>,
+    /// Items with this attribute (e.g. `#[no_hints]`) get no hints at all.
+    pub suppress_hint_attr: Option<SmolStr>,
+    pub type_hint_decoration: LabelDecoration,
+    pub parameter_hint_decoration: LabelDecoration,
+    pub chaining_hint_decoration: LabelDecoration,
 }
 
 impl Default for InlayHintsConfig {
     fn default() -> Self {
-        Self { type_hints: true, parameter_hints: true, chaining_hints: true, max_length: None }
+        Self {
+            type_hints: true,
+            type_hint_verbosity: HintVerbosity::Full,
+            parameter_hints: true,
+            chaining_hints: true,
+            max_length: None,
+            max_type_depth: None,
+            combine_consecutive_param_hints: false,
+            merge_overlapping_hints: false,
+            question_mark_hints: false,
+            await_hints: false,
+            tuple_field_hints: false,
+            literal_width_hints: false,
+            self_type_hint: false,
+            closure_capture_hints: false,
+            type_hint_denylist: Vec::new(),
+            hide_unit_type_hints: false,
+            layout_hints: false,
+            pattern_variant_hints: false,
+            explicit_ref_binding_hints: false,
+            chaining_hints_show_trait: false,
+            always_hint_bool_literals: false,
+            mutability_style: MutabilityStyle::Standard,
+            type_arg_separator: ", ".into(),
+            smart_pointer_style: SmartPtrStyle::Full,
+            normalize_projection_hints: false,
+            max_hints_per_line: None,
+            suppress_hint_attr: None,
+            type_hint_decoration: LabelDecoration::default(),
+            parameter_hint_decoration: LabelDecoration::default(),
+            chaining_hint_decoration: LabelDecoration::default(),
+        }
+    }
+}
+
+impl InlayHintsConfig {
+    /// A default config with exactly the hint kinds in `kinds` enabled.
+    pub fn with_kinds(kinds: HintKinds) -> Self {
+        Self {
+            type_hints: kinds.contains(HintKinds::TYPE),
+            parameter_hints: kinds.contains(HintKinds::PARAMETER),
+            chaining_hints: kinds.contains(HintKinds::CHAINING),
+            question_mark_hints: kinds.contains(HintKinds::QUESTION_MARK),
+            await_hints: kinds.contains(HintKinds::AWAIT),
+            tuple_field_hints: kinds.contains(HintKinds::TUPLE_FIELD),
+            literal_width_hints: kinds.contains(HintKinds::LITERAL_WIDTH),
+            self_type_hint: kinds.contains(HintKinds::SELF_TYPE),
+            closure_capture_hints: kinds.contains(HintKinds::CLOSURE_CAPTURE),
+            ..Self::default()
+        }
+    }
+}
+
+/// A bit-set of hint kinds, mirroring the `bool` toggles of `InlayHintsConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HintKinds(u16);
+
+impl HintKinds {
+    pub const EMPTY: HintKinds = HintKinds(0);
+    pub const TYPE: HintKinds = HintKinds(1 << 0);
+    pub const PARAMETER: HintKinds = HintKinds(1 << 1);
+    pub const CHAINING: HintKinds = HintKinds(1 << 2);
+    pub const QUESTION_MARK: HintKinds = HintKinds(1 << 3);
+    pub const AWAIT: HintKinds = HintKinds(1 << 4);
+    pub const TUPLE_FIELD: HintKinds = HintKinds(1 << 5);
+    pub const SELF_TYPE: HintKinds = HintKinds(1 << 6);
+    pub const CLOSURE_CAPTURE: HintKinds = HintKinds(1 << 7);
+    pub const LITERAL_WIDTH: HintKinds = HintKinds(1 << 8);
+
+    pub const fn union(self, other: HintKinds) -> HintKinds {
+        HintKinds(self.0 | other.0)
+    }
+
+    pub fn contains(self, other: HintKinds) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for HintKinds {
+    type Output = HintKinds;
+    fn bitor(self, other: HintKinds) -> HintKinds {
+        self.union(other)
+    }
+}
+
+/// Text baked around a hint label, for editors which render hints verbatim
+/// (e.g. `: i32` or `foo:`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LabelDecoration {
+    pub prefix: String,
+    pub suffix: String,
+}
+
+impl LabelDecoration {
+    fn decorate(&self, label: &SmolStr) -> SmolStr {
+        if self.prefix.is_empty() && self.suffix.is_empty() {
+            return label.clone();
+        }
+        format!("{}{}{}", self.prefix, label, self.suffix).into()
+    }
+}
+
+/// Which type hints of bindings are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintVerbosity {
+    /// No binding type hints at all.
+    Off,
+    /// Only hints for types which can't be read off the initializer, as with
+    /// `let v = collect_stuff();` but not `let v = Vec::<i32>::new();`.
+    Minimal,
+    /// All binding type hints.
+    Full,
+}
+
+impl Default for HintVerbosity {
+    fn default() -> Self {
+        HintVerbosity::Full
+    }
+}
+
+/// How smart pointers are shown in type labels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SmartPtrStyle {
+    /// `Arc<Mutex<T>>`
+    Full,
+    /// `⟪Arc⟫Mutex<T>`
+    Abbreviated,
+}
+
+impl Default for SmartPtrStyle {
+    fn default() -> Self {
+        SmartPtrStyle::Full
     }
 }
 
@@ -30,7 +224,7 @@ pub enum InlayKind {
     ChainingHint,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct InlayHint {
     pub range: TextRange,
     pub kind: InlayKind,
@@ -62,25 +256,206 @@ pub(crate) fn inlay_hints(
     file_id: FileId,
     config: &InlayHintsConfig,
 ) -> Vec<InlayHint> {
-    let _p = profile("inlay_hints");
+    inlay_hints_with(db, file_id, config, |_| true)
+}
+
+/// Like `inlay_hints`, but only keeps the hints for which `post` returns `true`.
+/// `post` is called on each hint as it is collected, before overlapping hints
+/// are merged and labels are decorated.
+pub(crate) fn inlay_hints_with(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &InlayHintsConfig,
+    post: impl FnMut(&InlayHint) -> bool,
+) -> Vec<InlayHint> {
+    let sema = Semantics::new(db);
+    collect_hints(&sema, file_id, config, &|ty| type_label(&sema, config, ty), post)
+}
+
+/// Like `inlay_hints`, but the labels of type and chaining hints are produced
+/// by `fmt` instead of the configured type display.
+pub(crate) fn inlay_hints_with_formatter(
+    db: &RootDatabase,
+    file_id: FileId,
+    config: &InlayHintsConfig,
+    fmt: impl Fn(&Type) -> String,
+) -> Vec<InlayHint> {
+    let sema = Semantics::new(db);
+    collect_hints(&sema, file_id, config, &fmt, |_| true)
+}
+
+/// Like `inlay_hints`, but only computes the hints inside the innermost
+/// function containing `position`.
+pub(crate) fn inlay_hints_for_function_at(
+    db: &RootDatabase,
+    position: FilePosition,
+    config: &InlayHintsConfig,
+) -> Vec<InlayHint> {
     let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let fn_def = match find_node_at_offset::<ast::FnDef>(file.syntax(), position.offset) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let mut res = hints_for_item(
+        &sema,
+        config,
+        &|ty| type_label(&sema, config, ty),
+        &mut |_| true,
+        fn_def.syntax(),
+    );
+    if let Some(max) = config.max_hints_per_line {
+        res = cap_hints_per_line(res, max, &db.line_index(position.file_id));
+    }
+    res
+}
+
+fn collect_hints(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    mut post: impl FnMut(&InlayHint) -> bool,
+) -> Vec<InlayHint> {
+    let _p = profile("inlay_hints");
     let file = sema.parse(file_id);
 
     let mut res = Vec::new();
-    for node in file.syntax().descendants() {
+    for item in file.syntax().children() {
+        res.extend(hints_for_item(sema, config, fmt, &mut post, &item));
+    }
+    if let Some(max) = config.max_hints_per_line {
+        res = cap_hints_per_line(res, max, &sema.db.line_index(file_id));
+    }
+    res
+}
+
+/// Drops all but the leftmost `max` hints of every line, keeping the order of
+/// the remaining ones.
+fn cap_hints_per_line(hints: Vec<InlayHint>, max: usize, line_index: &LineIndex) -> Vec<InlayHint> {
+    let mut by_position = (0..hints.len())
+        .map(|idx| {
+            let start = hints[idx].range.start();
+            (line_index.line_col(start).line, start, idx)
+        })
+        .collect::<Vec<_>>();
+    by_position.sort();
+
+    let mut keep = vec![false; hints.len()];
+    let mut line = None;
+    let mut on_line = 0;
+    for (hint_line, _, idx) in by_position {
+        if line != Some(hint_line) {
+            line = Some(hint_line);
+            on_line = 0;
+        }
+        if on_line < max {
+            keep[idx] = true;
+            on_line += 1;
+        }
+    }
+    hints
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(hint, keep)| if keep { Some(hint) } else { None })
+        .collect()
+}
+
+fn hints_for_item(
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    post: &mut dyn FnMut(&InlayHint) -> bool,
+    item: &SyntaxNode,
+) -> Vec<InlayHint> {
+    let mut res = Vec::new();
+    if is_hint_suppressed(config, item) {
+        return res;
+    }
+    let mut preorder = item.preorder();
+    while let Some(event) = preorder.next() {
+        let node = match event {
+            WalkEvent::Enter(node) => node,
+            WalkEvent::Leave(_) => continue,
+        };
+        if has_suppress_hint_attr(config, &node) {
+            preorder.skip_subtree();
+            continue;
+        }
+        let start = res.len();
         if let Some(expr) = ast::Expr::cast(node.clone()) {
-            get_chaining_hints(&mut res, &sema, config, expr);
+            get_chaining_hints(&mut res, sema, config, fmt, expr);
         }
 
         match_ast! {
             match node {
-                ast::CallExpr(it) => { get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it)); },
-                ast::MethodCallExpr(it) => { get_param_name_hints(&mut res, &sema, config, ast::Expr::from(it)); },
-                ast::BindPat(it) => { get_bind_pat_hints(&mut res, &sema, config, it); },
+                ast::CallExpr(it) => { get_param_name_hints(&mut res, sema, config, ast::Expr::from(it)); },
+                ast::MethodCallExpr(it) => { get_param_name_hints(&mut res, sema, config, ast::Expr::from(it)); },
+                ast::BindPat(it) => { get_bind_pat_hints(&mut res, sema, config, fmt, it); },
+                ast::TryExpr(it) => { get_question_mark_hints(&mut res, sema, config, fmt, it); },
+                ast::AwaitExpr(it) => { get_await_hints(&mut res, sema, config, fmt, it); },
+                ast::FieldExpr(it) => { get_tuple_field_hints(&mut res, sema, config, fmt, it); },
+                ast::Literal(it) => { get_literal_width_hints(&mut res, sema, config, fmt, it); },
+                ast::SelfParam(it) => { get_self_type_hint(&mut res, sema, config, fmt, it); },
+                ast::LambdaExpr(it) => { get_closure_capture_hints(&mut res, sema, config, it); },
                 _ => (),
             }
         }
+        let new_hints = res.split_off(start);
+        res.extend(new_hints.into_iter().filter(|hint| post(hint)));
     }
+    if config.merge_overlapping_hints {
+        res = merge_overlapping_hints(res);
+    }
+    for hint in res.iter_mut() {
+        let decoration = match hint.kind {
+            InlayKind::TypeHint => &config.type_hint_decoration,
+            InlayKind::ParameterHint => &config.parameter_hint_decoration,
+            InlayKind::ChainingHint => &config.chaining_hint_decoration,
+        };
+        hint.label = decoration.decorate(&hint.label);
+    }
+    res
+}
+
+/// Whether `node` is inside an item carrying `config.suppress_hint_attr`.
+fn is_hint_suppressed(config: &InlayHintsConfig, node: &SyntaxNode) -> bool {
+    node.ancestors().any(|it| has_suppress_hint_attr(config, &it))
+}
+
+/// Whether `node` is an item carrying `config.suppress_hint_attr`.
+fn has_suppress_hint_attr(config: &InlayHintsConfig, node: &SyntaxNode) -> bool {
+    let attr = match &config.suppress_hint_attr {
+        Some(it) => it,
+        None => return false,
+    };
+    ast::ModuleItem::cast(node.clone()).map_or(false, |item| item.has_atom_attr(attr))
+}
+
+/// Renders `source` with every hint inlined as a `/* label */` comment:
+/// parameter hints go before their argument, other hints after their node.
+/// Hints at the same offset keep their relative order from `hints`.
+pub fn render_hints(source: &str, hints: &[InlayHint]) -> String {
+    let mut inserts = hints
+        .iter()
+        .map(|hint| {
+            let offset = match hint.kind {
+                InlayKind::ParameterHint => hint.range.start(),
+                InlayKind::TypeHint | InlayKind::ChainingHint => hint.range.end(),
+            };
+            (usize::from(offset), format!("/* {} */", hint.label))
+        })
+        .collect::<Vec<_>>();
+    inserts.sort_by_key(|(offset, _)| *offset);
+
+    let mut res = String::with_capacity(source.len());
+    let mut last = 0;
+    for (offset, comment) in inserts {
+        res.push_str(&source[last..offset]);
+        res.push_str(&comment);
+        last = offset;
+    }
+    res.push_str(&source[last..]);
     res
 }
 
@@ -88,6 +463,7 @@ fn get_chaining_hints(
     acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
     config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
     expr: ast::Expr,
 ) -> Option<()> {
     if !config.chaining_hints {
@@ -124,7 +500,12 @@ fn get_chaining_hints(
                 }
             }
         }
-        let label = ty.display_truncated(sema.db, config.max_length).to_string();
+        let mut label = fmt(&ty);
+        if config.chaining_hints_show_trait {
+            if let Some(trait_) = method_call_trait(sema, &expr) {
+                label = format!("{} (via {})", label, trait_.name(sema.db));
+            }
+        }
         acc.push(InlayHint {
             range: expr.syntax().text_range(),
             kind: InlayKind::ChainingHint,
@@ -134,6 +515,19 @@ fn get_chaining_hints(
     Some(())
 }
 
+/// Returns the trait providing the method called by `expr`, if any.
+fn method_call_trait(sema: &Semantics<RootDatabase>, expr: &ast::Expr) -> Option<Trait> {
+    let call = match expr {
+        ast::Expr::MethodCallExpr(it) => it,
+        _ => return None,
+    };
+    let func = sema.resolve_method_call(call)?;
+    match func.as_assoc_item(sema.db)?.container(sema.db) {
+        AssocItemContainer::Trait(it) => Some(it),
+        AssocItemContainer::ImplDef(_) => None,
+    }
+}
+
 fn get_param_name_hints(
     acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
@@ -162,21 +556,76 @@ fn get_param_name_hints(
         .iter()
         .skip(n_params_to_skip)
         .zip(args)
-        .filter(|(param, arg)| should_show_param_name_hint(sema, &fn_signature, param, &arg))
+        .filter(|(param, arg)| {
+            should_show_param_name_hint(sema, config, &fn_signature, param, &arg)
+        })
         .map(|(param_name, arg)| InlayHint {
             range: arg.syntax().text_range(),
             kind: InlayKind::ParameterHint,
             label: param_name.into(),
         });
 
-    acc.extend(hints);
+    if config.combine_consecutive_param_hints {
+        acc.extend(combine_same_line_hints(&expr, hints));
+    } else {
+        acc.extend(hints);
+    }
     Some(())
 }
 
+/// Merges runs of adjacent parameter hints of `call` whose arguments are on
+/// the same line into one hint covering all of them.
+fn combine_same_line_hints(
+    call: &ast::Expr,
+    hints: impl Iterator<Item = InlayHint>,
+) -> Vec<InlayHint> {
+    let call_start = call.syntax().text_range().start();
+    let text = call.syntax().text();
+    let mut res: Vec<InlayHint> = Vec::new();
+    for hint in hints {
+        if let Some(prev) = res.last_mut() {
+            let between = TextRange::new(prev.range.end(), hint.range.start()) - call_start;
+            if !text.slice(between).contains_char('\n') {
+                prev.range = prev.range.cover(hint.range);
+                prev.label = format!("{}, {}", prev.label, hint.label).into();
+                continue;
+            }
+        }
+        res.push(hint);
+    }
+    res
+}
+
+/// Folds every non-parameter hint into the parameter hint with the same
+/// range, if there is one, as in `name: type`.
+fn merge_overlapping_hints(hints: Vec<InlayHint>) -> Vec<InlayHint> {
+    let mut res: Vec<InlayHint> = Vec::with_capacity(hints.len());
+    for hint in hints {
+        let is_param = hint.kind == InlayKind::ParameterHint;
+        let other = res
+            .iter_mut()
+            .find(|it| it.range == hint.range && (it.kind == InlayKind::ParameterHint) != is_param);
+        match other {
+            Some(other) => {
+                let (name, ty) = if is_param {
+                    (&hint.label, &other.label)
+                } else {
+                    (&other.label, &hint.label)
+                };
+                other.label = format!("{}: {}", name, ty).into();
+                other.kind = InlayKind::ParameterHint;
+            }
+            None => res.push(hint),
+        }
+    }
+    res
+}
+
 fn get_bind_pat_hints(
     acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
     config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
     pat: ast::BindPat,
 ) -> Option<()> {
     if !config.type_hints {
@@ -188,132 +637,553 @@ fn get_bind_pat_hints(
     if should_not_display_type_hint(sema.db, &pat, &ty) {
         return None;
     }
+    match config.type_hint_verbosity {
+        HintVerbosity::Off => return None,
+        HintVerbosity::Minimal if is_type_obvious_from_initializer(sema.db, &pat, &ty) => {
+            return None
+        }
+        HintVerbosity::Minimal | HintVerbosity::Full => (),
+    }
+    if config.hide_unit_type_hints && ty.is_unit() {
+        return None;
+    }
+
+    // Match the type's own name, not the label, which may be truncated,
+    // abbreviated or rendered by a custom formatter.
+    let base_name = match ty.as_adt() {
+        Some(adt) => adt.name(sema.db).to_string(),
+        None => ty.display(sema.db).to_string(),
+    };
+    if config.type_hint_denylist.iter().any(|it| it.as_str() == base_name) {
+        return None;
+    }
+    let mut label = fmt(&ty);
+    if let Some(size) = ty.size_of().filter(|_| config.layout_hints) {
+        let unit = if size == 1 { "byte" } else { "bytes" };
+        label = format!("{} ({} {})", label, size, unit);
+    }
+    if let Some(variant) =
+        enclosing_variant_name(sema, &pat).filter(|_| config.pattern_variant_hints)
+    {
+        label = format!("{} ({})", label, variant);
+    }
+    if config.explicit_ref_binding_hints && pat.ref_token().is_some() {
+        let mode = if pat.mut_token().is_some() { "ref mut" } else { "ref" };
+        label = format!("{} {}", mode, label);
+    }
 
     acc.push(InlayHint {
         range: pat.syntax().text_range(),
         kind: InlayKind::TypeHint,
-        label: ty.display_truncated(sema.db, config.max_length).to_string().into(),
+        label: label.into(),
     });
     Some(())
 }
 
-fn pat_is_enum_variant(db: &RootDatabase, bind_pat: &ast::BindPat, pat_ty: &Type) -> bool {
-    if let Some(Adt::Enum(enum_data)) = pat_ty.as_adt() {
-        let pat_text = bind_pat.to_string();
-        enum_data
-            .variants(db)
-            .into_iter()
-            .map(|variant| variant.name(db).to_string())
-            .any(|enum_name| enum_name == pat_text)
+fn type_label(sema: &Semantics<RootDatabase>, config: &InlayHintsConfig, ty: &Type) -> String {
+    let normalizer = ty.projection_normalizer(sema.db);
+    let label = ty
+        .display_truncated(sema.db, config.max_length)
+        .with_mutability_style(config.mutability_style)
+        .with_max_depth(config.max_type_depth)
+        .with_type_arg_separator(&config.type_arg_separator);
+    let label = if config.normalize_projection_hints {
+        label.with_projection_normalizer(Some(&normalizer)).to_string()
     } else {
-        false
+        label.to_string()
+    };
+    match config.smart_pointer_style {
+        SmartPtrStyle::Full => label,
+        SmartPtrStyle::Abbreviated => abbreviate_smart_pointers(&label),
     }
 }
 
-fn should_not_display_type_hint(db: &RootDatabase, bind_pat: &ast::BindPat, pat_ty: &Type) -> bool {
-    if pat_ty.is_unknown() {
-        return true;
+const SMART_POINTERS: &[&str] = &["Box", "Rc", "Arc"];
+
+/// Rewrites every `Arc<T>` (and the like) in `label` as `⟪Arc⟫T`.
+fn abbreviate_smart_pointers(label: &str) -> String {
+    let mut res = String::with_capacity(label.len());
+    let mut ident = String::new();
+    // For each `<` still open, whether it belongs to an abbreviated pointer.
+    let mut open = Vec::new();
+    let mut prev = None;
+    for c in label.chars() {
+        let before = prev.replace(c);
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+            continue;
+        }
+        match c {
+            '<' if SMART_POINTERS.contains(&ident.as_str()) => {
+                res.push('⟪');
+                res.push_str(&ident);
+                res.push('⟫');
+                ident.clear();
+                open.push(true);
+                continue;
+            }
+            '<' => open.push(false),
+            // `->` of `fn` types doesn't close anything
+            '>' if before != Some('-') && open.pop() == Some(true) => {
+                res.push_str(&ident);
+                ident.clear();
+                continue;
+            }
+            _ => (),
+        }
+        res.push_str(&ident);
+        ident.clear();
+        res.push(c);
     }
+    res.push_str(&ident);
+    res
+}
 
-    if let Some(Adt::Struct(s)) = pat_ty.as_adt() {
-        if s.fields(db).is_empty() && s.name(db).to_string() == bind_pat.to_string() {
-            return true;
-        }
+fn get_question_mark_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    expr: ast::TryExpr,
+) -> Option<()> {
+    if !config.question_mark_hints {
+        return None;
     }
 
-    for node in bind_pat.syntax().ancestors() {
+    let question_mark = expr.question_mark_token()?;
+    let error_ty = try_return_type(sema, expr.syntax())?.try_error_type(sema.db)?;
+    if error_ty.is_unknown() {
+        return None;
+    }
+
+    acc.push(InlayHint {
+        range: question_mark.text_range(),
+        kind: InlayKind::TypeHint,
+        label: fmt(&error_ty).into(),
+    });
+    Some(())
+}
+
+/// The type returned early by a `?` in `node`: the return type of the
+/// enclosing function or closure, or the type of the enclosing `try` block.
+fn try_return_type(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> Option<Type> {
+    for ancestor in node.ancestors() {
         match_ast! {
-            match node {
-                ast::LetStmt(it) => {
-                    return it.ascribed_type().is_some()
-                },
-                ast::Param(it) => {
-                    return it.ascribed_type().is_some()
-                },
-                ast::MatchArm(_it) => {
-                    return pat_is_enum_variant(db, bind_pat, pat_ty);
-                },
-                ast::IfExpr(it) => {
-                    return it.condition().and_then(|condition| condition.pat()).is_some()
-                        && pat_is_enum_variant(db, bind_pat, pat_ty);
-                },
-                ast::WhileExpr(it) => {
-                    return it.condition().and_then(|condition| condition.pat()).is_some()
-                        && pat_is_enum_variant(db, bind_pat, pat_ty);
+            match ancestor {
+                ast::FnDef(it) => return Some(sema.to_def(&it)?.ret_type(sema.db)),
+                ast::LambdaExpr(it) => return sema.type_of_expr(&it.body()?),
+                ast::EffectExpr(it) => match it.effect() {
+                    ast::Effect::Try(_) => return sema.type_of_expr(&it.into()),
+                    ast::Effect::Async(_) => return None,
+                    ast::Effect::Unsafe(_) | ast::Effect::Label(_) => (),
                 },
                 _ => (),
             }
         }
     }
-    false
+    None
 }
 
-fn should_show_param_name_hint(
+fn get_await_hints(
+    acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
-    fn_signature: &FunctionSignature,
-    param_name: &str,
-    argument: &ast::Expr,
-) -> bool {
-    let param_name = param_name.trim_start_matches('_');
-    if param_name.is_empty()
-        || Some(param_name) == fn_signature.name.as_ref().map(|s| s.trim_start_matches('_'))
-        || is_argument_similar_to_param_name(sema, argument, param_name)
-        || param_name.starts_with("ra_fixture")
-    {
-        return false;
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    expr: ast::AwaitExpr,
+) -> Option<()> {
+    if !config.await_hints {
+        return None;
     }
 
-    let parameters_len = if fn_signature.has_self_param {
-        fn_signature.parameters.len() - 1
-    } else {
-        fn_signature.parameters.len()
-    };
+    let range = expr.dot_token()?.text_range().cover(expr.await_token()?.text_range());
+    let output_ty = sema.type_of_expr(&ast::Expr::from(expr))?;
+    if output_ty.is_unknown() {
+        return None;
+    }
 
-    // avoid displaying hints for common functions like map, filter, etc.
-    // or other obvious words used in std
-    !(parameters_len == 1 && is_obvious_param(param_name))
+    acc.push(InlayHint { range, kind: InlayKind::TypeHint, label: fmt(&output_ty).into() });
+    Some(())
 }
 
-fn is_argument_similar_to_param_name(
+fn get_tuple_field_hints(
+    acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
-    argument: &ast::Expr,
-    param_name: &str,
-) -> bool {
-    if is_enum_name_similar_to_param_name(sema, argument, param_name) {
-        return true;
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    expr: ast::FieldExpr,
+) -> Option<()> {
+    if !config.tuple_field_hints {
+        return None;
     }
-    match get_string_representation(argument) {
-        None => false,
-        Some(repr) => {
-            let argument_string = repr.trim_start_matches('_');
-            argument_string.starts_with(param_name) || argument_string.ends_with(param_name)
-        }
+
+    let index = match expr.field_access()? {
+        ast::FieldKind::Index(it) => it,
+        ast::FieldKind::Name(_) => return None,
+    };
+    match sema.resolve_field(&expr)?.parent_def(sema.db) {
+        VariantDef::Struct(it) if it.kind(sema.db) == StructKind::Tuple => (),
+        _ => return None,
+    }
+    let ty = sema.type_of_expr(&ast::Expr::from(expr))?;
+    if ty.is_unknown() {
+        return None;
     }
+
+    acc.push(InlayHint {
+        range: index.text_range(),
+        kind: InlayKind::TypeHint,
+        label: fmt(&ty).into(),
+    });
+    Some(())
 }
 
-fn is_enum_name_similar_to_param_name(
+fn get_literal_width_hints(
+    acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
-    argument: &ast::Expr,
-    param_name: &str,
-) -> bool {
-    match sema.type_of_expr(argument).and_then(|t| t.as_adt()) {
-        Some(Adt::Enum(e)) => to_lower_snake_case(&e.name(sema.db).to_string()) == param_name,
-        _ => false,
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    literal: ast::Literal,
+) -> Option<()> {
+    if !config.literal_width_hints {
+        return None;
     }
-}
-
-fn get_string_representation(expr: &ast::Expr) -> Option<String> {
-    match expr {
-        ast::Expr::MethodCallExpr(method_call_expr) => {
-            Some(method_call_expr.name_ref()?.to_string())
-        }
-        ast::Expr::RefExpr(ref_expr) => get_string_representation(&ref_expr.expr()?),
-        _ => Some(expr.to_string()),
+    match literal.kind() {
+        ast::LiteralKind::IntNumber { suffix: None }
+        | ast::LiteralKind::FloatNumber { suffix: None } => {}
+        _ => return None,
     }
-}
 
-fn is_obvious_param(param_name: &str) -> bool {
-    let is_obvious_param_name =
+    let ty = sema.type_of_expr(&literal.clone().into())?;
+    if ty.is_unknown() || ty.is_literal_fallback() {
+        return None;
+    }
+    acc.push(InlayHint {
+        range: literal.syntax().text_range(),
+        kind: InlayKind::TypeHint,
+        label: fmt(&ty).into(),
+    });
+    Some(())
+}
+
+fn get_self_type_hint(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    fmt: &dyn Fn(&Type) -> String,
+    param: ast::SelfParam,
+) -> Option<()> {
+    if !config.self_type_hint {
+        return None;
+    }
+
+    let self_token = param.self_token()?;
+    let ty = sema.type_of_self(&param)?;
+    if ty.is_unknown() {
+        return None;
+    }
+
+    acc.push(InlayHint {
+        range: self_token.text_range(),
+        kind: InlayKind::TypeHint,
+        label: fmt(&ty).into(),
+    });
+    Some(())
+}
+
+/// There is no capture analysis in HIR yet, so captures are approximated:
+/// every local used in the body but declared outside of the closure is
+/// captured, by move if the closure is `move` or the local is passed by value
+/// and not `Copy`, by reference otherwise.
+fn get_closure_capture_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    closure: ast::LambdaExpr,
+) -> Option<()> {
+    if !config.closure_capture_hints {
+        return None;
+    }
+
+    let param_list = closure.param_list()?;
+    let body = closure.body()?;
+    let closure_range = closure.syntax().text_range();
+    let is_move = closure.move_token().is_some();
+
+    let mut captures: Vec<(Local, bool)> = Vec::new();
+    for path_expr in body.syntax().descendants().filter_map(ast::PathExpr::cast) {
+        let local = match sema.resolve_path(&path_expr.path()?) {
+            Some(PathResolution::Local(it)) => it,
+            _ => continue,
+        };
+        let decl_range = match local.source(sema.db).value {
+            Either::Left(pat) => pat.syntax().text_range(),
+            Either::Right(param) => param.syntax().text_range(),
+        };
+        if closure_range.contains_range(decl_range) {
+            continue;
+        }
+        let by_move = if is_move {
+            true
+        } else if is_used_by_value(sema, &path_expr) {
+            // Without the `Copy` lang item, a move can't be told apart from a
+            // copy, so show no hint rather than a wrong one.
+            !local.ty(sema.db).try_is_copy(sema.db)?
+        } else {
+            false
+        };
+        match captures.iter_mut().find(|(it, _)| *it == local) {
+            Some((_, moved)) => *moved |= by_move,
+            None => captures.push((local, by_move)),
+        }
+    }
+    if captures.is_empty() {
+        return None;
+    }
+
+    let names = |by_move: bool| {
+        captures
+            .iter()
+            .filter(|(_, moved)| *moved == by_move)
+            .map(|(local, _)| local.name(sema.db).map(|it| it.to_string()).unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    let label = [("move", names(true)), ("ref", names(false))]
+        .iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(mode, names)| format!("{}: {}", mode, names.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    acc.push(InlayHint {
+        range: param_list.syntax().text_range(),
+        kind: InlayKind::TypeHint,
+        label: label.into(),
+    });
+    Some(())
+}
+
+/// Whether the value of `expr` is moved out, rather than borrowed or copied
+/// into a place expression.
+fn is_used_by_value(sema: &Semantics<RootDatabase>, expr: &ast::PathExpr) -> bool {
+    let parent = match expr.syntax().parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    match_ast! {
+        match parent {
+            ast::ArgList(_it) => true,
+            ast::LetStmt(it) => it.initializer().map_or(false, |init| init.syntax() == expr.syntax()),
+            ast::ReturnExpr(_it) => true,
+            ast::LambdaExpr(_it) => true,
+            ast::MethodCallExpr(it) => {
+                it.expr().map_or(false, |receiver| receiver.syntax() == expr.syntax())
+                    && sema
+                        .resolve_method_call(&it)
+                        .and_then(|func| func.self_param_access(sema.db))
+                        == Some(Access::Owned)
+            },
+            _ => false,
+        }
+    }
+}
+
+fn pat_is_enum_variant(db: &RootDatabase, bind_pat: &ast::BindPat, pat_ty: &Type) -> bool {
+    if let Some(Adt::Enum(enum_data)) = pat_ty.as_adt() {
+        let pat_text = bind_pat.to_string();
+        enum_data
+            .variants(db)
+            .into_iter()
+            .map(|variant| variant.name(db).to_string())
+            .any(|enum_name| enum_name == pat_text)
+    } else {
+        false
+    }
+}
+
+/// The name of the enum variant matched by the nearest tuple struct or record
+/// pattern enclosing `bind_pat`, if any.
+fn enclosing_variant_name(
+    sema: &Semantics<RootDatabase>,
+    bind_pat: &ast::BindPat,
+) -> Option<String> {
+    let parent = bind_pat.syntax().ancestors().skip(1).find(|it| {
+        ast::TupleStructPat::can_cast(it.kind()) || ast::RecordPat::can_cast(it.kind())
+    })?;
+    let path = match_ast! {
+        match parent {
+            ast::TupleStructPat(it) => it.path()?,
+            ast::RecordPat(it) => it.path()?,
+            _ => return None,
+        }
+    };
+    let pat_text = path.segment()?.name_ref()?.text().to_string();
+    let pat_ty = sema.type_of_pat(&ast::Pat::cast(parent)?)?;
+    match pat_ty.as_adt() {
+        Some(Adt::Enum(enum_data)) => enum_data
+            .variants(sema.db)
+            .into_iter()
+            .map(|variant| variant.name(sema.db).to_string())
+            .find(|variant_name| *variant_name == pat_text),
+        _ => None,
+    }
+}
+
+fn should_not_display_type_hint(db: &RootDatabase, bind_pat: &ast::BindPat, pat_ty: &Type) -> bool {
+    if pat_ty.is_unknown() {
+        return true;
+    }
+
+    if let Some(Adt::Struct(s)) = pat_ty.as_adt() {
+        if s.fields(db).is_empty() && s.name(db).to_string() == bind_pat.to_string() {
+            return true;
+        }
+    }
+
+    for node in bind_pat.syntax().ancestors() {
+        match_ast! {
+            match node {
+                ast::LetStmt(it) => {
+                    return it.ascribed_type().is_some()
+                },
+                ast::Param(it) => {
+                    return it.ascribed_type().is_some()
+                },
+                ast::MatchArm(_it) => {
+                    return pat_is_enum_variant(db, bind_pat, pat_ty);
+                },
+                ast::IfExpr(it) => {
+                    return it.condition().and_then(|condition| condition.pat()).is_some()
+                        && pat_is_enum_variant(db, bind_pat, pat_ty);
+                },
+                ast::WhileExpr(it) => {
+                    return it.condition().and_then(|condition| condition.pat()).is_some()
+                        && pat_is_enum_variant(db, bind_pat, pat_ty);
+                },
+                _ => (),
+            }
+        }
+    }
+    false
+}
+
+/// Whether the type of `bind_pat` can be read off the initializer of its
+/// `let`: literals, struct literals, casts, and calls of tuple struct
+/// constructors or associated functions of the type itself, like
+/// `Vec::<i32>::new()`.
+fn is_type_obvious_from_initializer(
+    db: &RootDatabase,
+    bind_pat: &ast::BindPat,
+    pat_ty: &Type,
+) -> bool {
+    let initializer = match bind_pat.syntax().parent().and_then(ast::LetStmt::cast) {
+        Some(let_stmt) => let_stmt.initializer(),
+        None => return false,
+    };
+    let call = match initializer {
+        Some(ast::Expr::Literal(_))
+        | Some(ast::Expr::RecordLit(_))
+        | Some(ast::Expr::CastExpr(_)) => return true,
+        Some(ast::Expr::CallExpr(it)) => it,
+        _ => return false,
+    };
+    let path = match call.expr() {
+        Some(ast::Expr::PathExpr(it)) => it.path(),
+        _ => None,
+    };
+    let type_path = match path {
+        Some(path) => path.qualifier().unwrap_or(path),
+        None => return false,
+    };
+    let type_name = type_path.segment().and_then(|it| it.name_ref());
+    match (type_name, pat_ty.as_adt()) {
+        (Some(type_name), Some(adt)) => type_name.text().as_str() == adt.name(db).to_string(),
+        _ => false,
+    }
+}
+
+fn should_show_param_name_hint(
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    fn_signature: &FunctionSignature,
+    param_name: &str,
+    argument: &ast::Expr,
+) -> bool {
+    let param_name = param_name.trim_start_matches('_');
+    let fn_name = fn_signature.name.as_ref().map(|s| s.trim_start_matches('_'));
+    if param_name.is_empty() || param_name.starts_with("ra_fixture") {
+        return false;
+    }
+
+    // `true` or `false` alone says nothing about what it switches on, so name
+    // it unless the parameter just repeats the function name (`frob(false)`)
+    // or is a single letter.
+    if config.always_hint_bool_literals && is_bool_literal(argument) {
+        return param_name.len() > 1 && Some(param_name) != fn_name;
+    }
+
+    if Some(param_name) == fn_name || is_argument_similar_to_param_name(sema, argument, param_name)
+    {
+        return false;
+    }
+
+    let parameters_len = if fn_signature.has_self_param {
+        fn_signature.parameters.len() - 1
+    } else {
+        fn_signature.parameters.len()
+    };
+
+    // avoid displaying hints for common functions like map, filter, etc.
+    // or other obvious words used in std
+    !(parameters_len == 1 && is_obvious_param(param_name))
+}
+
+fn is_argument_similar_to_param_name(
+    sema: &Semantics<RootDatabase>,
+    argument: &ast::Expr,
+    param_name: &str,
+) -> bool {
+    if is_enum_name_similar_to_param_name(sema, argument, param_name) {
+        return true;
+    }
+    match get_string_representation(argument) {
+        None => false,
+        Some(repr) => {
+            let argument_string = repr.trim_start_matches('_');
+            argument_string.starts_with(param_name) || argument_string.ends_with(param_name)
+        }
+    }
+}
+
+fn is_enum_name_similar_to_param_name(
+    sema: &Semantics<RootDatabase>,
+    argument: &ast::Expr,
+    param_name: &str,
+) -> bool {
+    match sema.type_of_expr(argument).and_then(|t| t.as_adt()) {
+        Some(Adt::Enum(e)) => to_lower_snake_case(&e.name(sema.db).to_string()) == param_name,
+        _ => false,
+    }
+}
+
+fn is_bool_literal(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Literal(lit) => matches!(lit.kind(), ast::LiteralKind::Bool(_)),
+        _ => false,
+    }
+}
+
+fn get_string_representation(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::MethodCallExpr(method_call_expr) => {
+            Some(method_call_expr.name_ref()?.to_string())
+        }
+        ast::Expr::RefExpr(ref_expr) => get_string_representation(&ref_expr.expr()?),
+        _ => Some(expr.to_string()),
+    }
+}
+
+fn is_obvious_param(param_name: &str) -> bool {
+    let is_obvious_param_name =
         matches!(param_name, "predicate" | "value" | "pat" | "rhs" | "other");
     param_name.len() == 1 || is_obvious_param_name
 }
@@ -345,10 +1215,15 @@ fn get_fn_signature(sema: &Semantics<RootDatabase>, expr: &ast::Expr) -> Option<
 
 #[cfg(test)]
 mod tests {
-    use crate::inlay_hints::InlayHintsConfig;
+    use crate::inlay_hints::{
+        merge_overlapping_hints, render_hints, HintKinds, HintVerbosity, InlayHint,
+        InlayHintsConfig, InlayKind, LabelDecoration, SmartPtrStyle,
+    };
+    use hir::{HirDisplay, MutabilityStyle};
     use insta::assert_debug_snapshot;
+    use ra_syntax::TextRange;
 
-    use crate::mock_analysis::single_file;
+    use crate::mock_analysis::{analysis_and_position, single_file, MockAnalysis};
 
     #[test]
     fn param_hints_only() {
@@ -359,7 +1234,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: true, type_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { parameter_hints: true, type_hints: false, chaining_hints: false, max_length: None, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 69..70,
@@ -384,7 +1259,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: false, parameter_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_hints: false, parameter_hints: false, chaining_hints: false, max_length: None, ..Default::default() }).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -396,7 +1271,7 @@ mod tests {
                 let _x = foo(4, 4);
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ type_hints: true, parameter_hints: false, chaining_hints: false, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_hints: true, parameter_hints: false, chaining_hints: false, max_length: None, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 60..62,
@@ -610,6 +1485,39 @@ fn main() {
         );
     }
 
+    #[test]
+    fn closure_with_block_body() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let f = || { 42 };
+    let g = |x: i32| {
+        let y = x;
+        y
+    };
+}"#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 20..21,
+                kind: TypeHint,
+                label: "|| -> i32",
+            },
+            InlayHint {
+                range: 43..44,
+                kind: TypeHint,
+                label: "|…| -> i32",
+            },
+            InlayHint {
+                range: 70..71,
+                kind: TypeHint,
+                label: "i32",
+            },
+        ]
+        "###);
+    }
+
     #[test]
     fn for_expression() {
         let (analysis, file_id) = single_file(
@@ -1237,7 +2145,7 @@ fn main() {
                     .into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 147..172,
@@ -1267,7 +2175,7 @@ fn main() {
                 let c = A(B(C)).into_b().into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"[]"###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default() }).unwrap(), @r###"[]"###);
     }
 
     #[test]
@@ -1292,7 +2200,7 @@ fn main() {
                     .foo();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 143..190,
@@ -1330,7 +2238,7 @@ fn main() {
                     .into_c();
             }"#,
         );
-        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig{ parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None}).unwrap(), @r###"
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { parameter_hints: false, type_hints: false, chaining_hints: true, max_length: None, ..Default::default() }).unwrap(), @r###"
         [
             InlayHint {
                 range: 246..283,
@@ -1345,4 +2253,1149 @@ fn main() {
         ]
         "###);
     }
+
+    #[test]
+    fn combined_param_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn point(x: i32, y: i32, z: i32) {}
+fn main() {
+    point(1, 2, 3);
+}"#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_hints: false, chaining_hints: false, combine_consecutive_param_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 58..65,
+                kind: ParameterHint,
+                label: "x, y, z",
+            },
+        ]
+        "###);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_hints: false, chaining_hints: false, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 58..59,
+                kind: ParameterHint,
+                label: "x",
+            },
+            InlayHint {
+                range: 61..62,
+                kind: ParameterHint,
+                label: "y",
+            },
+            InlayHint {
+                range: 64..65,
+                kind: ParameterHint,
+                label: "z",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn post_processing_can_drop_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn foo(a: i32, b: i32) -> i32 { a + b }
+fn main() {
+    let _x = foo(4, 4);
+}"#,
+        );
+        let filtered = analysis
+            .inlay_hints_with(file_id, &InlayHintsConfig::default(), |hint| {
+                hint.kind != InlayKind::ParameterHint
+            })
+            .unwrap();
+        let disabled = analysis
+            .inlay_hints(
+                file_id,
+                &InlayHintsConfig { parameter_hints: false, ..Default::default() },
+            )
+            .unwrap();
+        assert_eq!(filtered, disabled);
+    }
+
+    #[test]
+    fn post_processing_sees_undecorated_labels() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let x = 92u32;
+    let y = 92u64;
+}"#,
+        );
+        let config = InlayHintsConfig {
+            type_hint_decoration: LabelDecoration { prefix: ": ".into(), ..Default::default() },
+            ..Default::default()
+        };
+        let hints =
+            analysis.inlay_hints_with(file_id, &config, |hint| hint.label == "u32").unwrap();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].label, ": u32");
+    }
+
+    #[test]
+    fn question_mark_hints() {
+        let mock = MockAnalysis::with_files(
+            r#"
+//- /main.rs
+use core::{convert::From, result::Result};
+struct MyErr;
+struct OtherErr;
+impl From<MyErr> for OtherErr { fn from(_: MyErr) -> OtherErr { OtherErr } }
+fn foo() -> Result<i32, MyErr> { loop {} }
+fn bar() -> Result<i32, OtherErr> {
+    let x = foo()?;
+    let y = (|| -> Result<i32, MyErr> { Result::Ok(foo()?) })()?;
+    Result::Ok(x)
+}
+
+//- /core/lib.rs
+pub mod result {
+    pub enum Result<T, E> { Ok(T), Err(E) }
+}
+pub mod convert {
+    pub trait From<T> { fn from(t: T) -> Self; }
+}
+pub mod ops {
+    pub trait Try {
+        type Ok;
+        type Error;
+    }
+    impl<T, E> Try for crate::result::Result<T, E> {
+        type Ok = T;
+        type Error = E;
+    }
+}
+"#,
+        );
+        let file_id = mock.id_of("/main.rs");
+        let analysis = mock.analysis();
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_hints: false, parameter_hints: false, chaining_hints: false, question_mark_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 247..248,
+                kind: TypeHint,
+                label: "OtherErr",
+            },
+            InlayHint {
+                range: 313..314,
+                kind: TypeHint,
+                label: "OtherErr",
+            },
+            InlayHint {
+                range: 306..307,
+                kind: TypeHint,
+                label: "MyErr",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn decorated_labels() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn foo(param: i32) -> i32 { param }
+fn main() {
+    let x = foo(4);
+}"#,
+        );
+        let config = InlayHintsConfig {
+            type_hint_decoration: LabelDecoration { prefix: ": ".into(), ..Default::default() },
+            parameter_hint_decoration: LabelDecoration { suffix: ":".into(), ..Default::default() },
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 56..57,
+                kind: TypeHint,
+                label: ": i32",
+            },
+            InlayHint {
+                range: 64..65,
+                kind: ParameterHint,
+                label: "param:",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn await_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[lang = "future_trait"]
+pub trait Future {
+    type Output;
+}
+fn foo() -> impl Future<Output = i32> { loop {} }
+async fn main() {
+    foo().await;
+}"#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig { type_hints: false, parameter_hints: false, chaining_hints: false, await_hints: true, ..Default::default() }).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 140..146,
+                kind: TypeHint,
+                label: "i32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn render_hints_inline() {
+        let source = r#"
+fn foo(param: i32) -> i32 { param }
+fn main() {
+    let x = foo(4);
+}"#;
+        let (analysis, file_id) = single_file(source);
+        let hints = analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap();
+        let text = analysis.file_text(file_id).unwrap();
+        assert_eq!(
+            render_hints(&text, &hints),
+            "fn foo(param: i32) -> i32 { param }\nfn main() {\n    let x/* i32 */ = foo(/* param */4);\n}"
+        );
+    }
+
+    #[test]
+    fn type_hint_denylist() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct String;
+impl String {
+    fn new() -> String { String }
+}
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn new() -> Vec<T> { loop {} }
+}
+fn main() {
+    let s = String::new();
+    let v = Vec::<i32>::new();
+}"#,
+        );
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            chaining_hints: false,
+            type_hint_denylist: vec!["String".into()],
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 184..185,
+                kind: TypeHint,
+                label: "Vec<i32>",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn type_hint_denylist_ignores_label_style() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Arc<T>(T);
+fn main() {
+    let a = Arc(0u8);
+    let n = 0u8;
+}"#,
+        );
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            type_hint_denylist: vec!["Arc".into()],
+            smart_pointer_style: SmartPtrStyle::Abbreviated,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 60..61,
+                kind: TypeHint,
+                label: "u8",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn hide_unit_type_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let x = ();
+    let y = loop {};
+}"#,
+        );
+        let config = InlayHintsConfig { hide_unit_type_hints: true, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 36..37,
+                kind: TypeHint,
+                label: "!",
+            },
+        ]
+        "###);
+
+        let hints = analysis
+            .inlay_hints_with_formatter(file_id, &config, |_, _| "unit or not".to_string())
+            .unwrap();
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].range, TextRange::at(36.into(), 1.into()));
+    }
+
+    #[test]
+    fn smart_pointer_style() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Arc<T>(T);
+impl<T> Arc<T> {
+    fn new(value: T) -> Arc<T> { Arc(value) }
+}
+fn main() {
+    let x = Arc::new(1i32);
+}"#,
+        );
+        let config = InlayHintsConfig { parameter_hints: false, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 103..104,
+                kind: TypeHint,
+                label: "Arc<i32>",
+            },
+        ]
+        "###);
+
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            smart_pointer_style: SmartPtrStyle::Abbreviated,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 103..104,
+                kind: TypeHint,
+                label: "⟪Arc⟫i32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn literal_width_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn take(b: u8) {}
+fn main() {
+    let a = 5;
+    let b: u8 = 5;
+    let c = 1.5;
+    let d: f32 = 1.5;
+    take(7);
+    let e = 7u16;
+}"#,
+        );
+        let config = InlayHintsConfig::with_kinds(HintKinds::LITERAL_WIDTH);
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 61..62,
+                kind: TypeHint,
+                label: "u8",
+            },
+            InlayHint {
+                range: 98..101,
+                kind: TypeHint,
+                label: "f32",
+            },
+            InlayHint {
+                range: 112..113,
+                kind: TypeHint,
+                label: "u8",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn minimal_type_hint_verbosity() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn new() -> Vec<T> { loop {} }
+}
+fn collect_stuff() -> Vec<u32> { loop {} }
+fn main() {
+    let x = 5;
+    let v = Vec::<i32>::new();
+    let w = collect_stuff();
+}"#,
+        );
+        let config =
+            InlayHintsConfig { type_hint_verbosity: HintVerbosity::Minimal, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 181..182,
+                kind: TypeHint,
+                label: "Vec<u32>",
+            },
+        ]
+        "###);
+
+        let config =
+            InlayHintsConfig { type_hint_verbosity: HintVerbosity::Off, ..Default::default() };
+        assert_eq!(analysis.inlay_hints(file_id, &config).unwrap(), vec![]);
+        assert_eq!(analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn hints_for_function_at() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+fn foo() {
+    let a = 1;
+}
+fn main() {
+    let b = <|>2;
+}"#,
+        );
+        let hints =
+            analysis.inlay_hints_for_function_at(position, &InlayHintsConfig::default()).unwrap();
+        assert_debug_snapshot!(hints, @r###"
+        [
+            InlayHint {
+                range: 48..49,
+                kind: TypeHint,
+                label: "i32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn layout_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S<T>(T);
+fn main() {
+    let x = 0u32;
+    let s = S(x);
+    let w = 0i128;
+}"#,
+        );
+        let config =
+            InlayHintsConfig { parameter_hints: false, layout_hints: true, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 36..37,
+                kind: TypeHint,
+                label: "u32 (4 bytes)",
+            },
+            InlayHint {
+                range: 54..55,
+                kind: TypeHint,
+                label: "S<u32>",
+            },
+            InlayHint {
+                range: 72..73,
+                kind: TypeHint,
+                label: "i128 (16 bytes)",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn pattern_variant_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+enum Option<T> { None, Some(T) }
+use Option::*;
+fn main() {
+    let opt = Some(0u32);
+    if let Some(x) = opt {}
+}"#,
+        );
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            pattern_variant_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 68..71,
+                kind: TypeHint,
+                label: "Option<u32>",
+            },
+            InlayHint {
+                range: 102..103,
+                kind: TypeHint,
+                label: "u32 (Some)",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn explicit_ref_binding_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let mut pair = (0u32, 'c');
+    let (ref mut a, b) = pair;
+}"#,
+        );
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            explicit_ref_binding_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 20..28,
+                kind: TypeHint,
+                label: "(u32, char)",
+            },
+            InlayHint {
+                range: 53..62,
+                kind: TypeHint,
+                label: "ref mut &mut u32",
+            },
+            InlayHint {
+                range: 64..65,
+                kind: TypeHint,
+                label: "char",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn chaining_hints_show_trait() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Chars;
+struct Rev<I>(I);
+trait Iterator {
+    fn rev(self) -> Rev<Self> { loop {} }
+}
+impl Iterator for Chars {}
+impl<I> Iterator for Rev<I> {}
+impl Chars {
+    fn new() -> Chars { Chars }
+    fn chars(self) -> Chars { self }
+}
+fn main() {
+    let c = Chars::new()
+        .chars()
+        .rev()
+        .rev();
+}"#,
+        );
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            type_hints: false,
+            chaining_hints: true,
+            chaining_hints_show_trait: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 259..303,
+                kind: ChainingHint,
+                label: "Rev<Chars> (via Iterator)",
+            },
+            InlayHint {
+                range: 259..288,
+                kind: ChainingHint,
+                label: "Chars",
+            },
+            InlayHint {
+                range: 259..271,
+                kind: ChainingHint,
+                label: "Chars",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn always_hint_bool_literals() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Test {}
+impl Test {
+    fn frob(&self, frob: bool) {}
+}
+fn set(value: bool) {}
+fn twiddle(twiddle: bool) {}
+fn main() {
+    let test = Test {};
+    test.frob(false);
+    set(true);
+    set(!false);
+    twiddle(true);
+}"#,
+        );
+        let config =
+            InlayHintsConfig { type_hints: false, chaining_hints: false, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"[]"###);
+
+        // A parameter repeating the function name still needs no hint, so
+        // `test.frob(false)` and `twiddle(true)` stay bare.
+        let config = InlayHintsConfig { always_hint_bool_literals: true, ..config };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 181..185,
+                kind: ParameterHint,
+                label: "value",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn mutability_style() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let mut y = 0u32;
+    let x = &mut y;
+}"#,
+        );
+        let config = InlayHintsConfig::default();
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 20..25,
+                kind: TypeHint,
+                label: "u32",
+            },
+            InlayHint {
+                range: 42..43,
+                kind: TypeHint,
+                label: "&mut u32",
+            },
+        ]
+        "###);
+
+        let config = InlayHintsConfig { mutability_style: MutabilityStyle::Compact, ..config };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 20..25,
+                kind: TypeHint,
+                label: "u32",
+            },
+            InlayHint {
+                range: 42..43,
+                kind: TypeHint,
+                label: "&m u32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn mutability_style_in_nested_types() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn f(s: &[&mut u32]) {
+    let t = s;
+    let u = [&mut 0u32];
+    let p = &u as *const [&mut u32; 1];
+}"#,
+        );
+        let config =
+            InlayHintsConfig { mutability_style: MutabilityStyle::Compact, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 31..32,
+                kind: TypeHint,
+                label: "&[&m u32]",
+            },
+            InlayHint {
+                range: 46..47,
+                kind: TypeHint,
+                label: "[&m u32; _]",
+            },
+            InlayHint {
+                range: 71..72,
+                kind: TypeHint,
+                label: "*const [&m u32; _]",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn suppress_hint_attr() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[no_hints]
+fn hidden() {
+    let x = 92u32;
+}
+fn shown() {
+    let y = 92u32;
+}"#,
+        );
+        let config =
+            InlayHintsConfig { suppress_hint_attr: Some("no_hints".into()), ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 68..69,
+                kind: TypeHint,
+                label: "u32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn suppress_hint_attr_on_nested_items() {
+        let (analysis, file_id) = single_file(
+            r#"
+mod m {
+    #[no_hints]
+    fn hidden() {
+        let x = 92u32;
+    }
+    fn shown() {
+        let y = 92u32;
+    }
+}
+#[no_hints]
+mod n {
+    fn hidden() {
+        let z = 92u32;
+    }
+}"#,
+        );
+        let config =
+            InlayHintsConfig { suppress_hint_attr: Some("no_hints".into()), ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 100..101,
+                kind: TypeHint,
+                label: "u32",
+            },
+        ]
+        "###);
+
+        let (analysis, position) = analysis_and_position(
+            r#"
+#[no_hints]
+mod n {
+    fn hidden() {
+        let z = <|>92u32;
+    }
+}"#,
+        );
+        assert!(analysis.inlay_hints_for_function_at(position, &config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn max_type_depth() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Smol<T>(T);
+fn main() {
+    let x = Smol(Smol(Smol(0u32)));
+}"#,
+        );
+        let config = InlayHintsConfig { max_type_depth: Some(1), ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 39..40,
+                kind: TypeHint,
+                label: "Smol<Smol<…>>",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn tuple_field_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct P(i32, bool);
+struct N { x: i32 }
+fn main() {
+    let p = P(1, true);
+    let n = N { x: 1 };
+    p.0;
+    n.x;
+    (1, 2).0;
+}"#,
+        );
+        let config = InlayHintsConfig {
+            type_hints: false,
+            parameter_hints: false,
+            chaining_hints: false,
+            tuple_field_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 107..108,
+                kind: TypeHint,
+                label: "i32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn let_else_bindings() {
+        let (analysis, file_id) = single_file(
+            r#"
+enum Option<T> { None, Some(T) }
+use Option::*;
+fn f(opt: Option<u32>) {
+    let Some(x) = opt else { return };
+}"#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 86..87,
+                kind: TypeHint,
+                label: "u32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn let_else_branch_bindings() {
+        let (analysis, file_id) = single_file(
+            r#"
+enum Option<T> { None, Some(T) }
+use Option::*;
+fn f(opt: Option<u32>) {
+    let Some(x) = opt else {
+        let fallback = 0u8;
+        return;
+    };
+}"#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 86..87,
+                kind: TypeHint,
+                label: "u32",
+            },
+            InlayHint {
+                range: 114..122,
+                kind: TypeHint,
+                label: "u8",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn type_arg_separator() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct HashMap<K, V>(K, V);
+struct String;
+fn main() {
+    let map = HashMap(1i32, String);
+}"#,
+        );
+        let config = InlayHintsConfig {
+            parameter_hints: false,
+            type_arg_separator: ",".into(),
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 63..66,
+                kind: TypeHint,
+                label: "HashMap<i32,String>",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn self_type_hint() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Foo<T>(T);
+impl<T> Foo<T> {
+    fn get(&self) {}
+}"#,
+        );
+        let config = InlayHintsConfig { self_type_hint: true, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 47..51,
+                kind: TypeHint,
+                label: "&Foo<T>",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn closure_capture_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[lang = "copy"]
+trait Copy {}
+struct S;
+fn consume(s: S) {}
+fn main() {
+    let x = S;
+    let y = S;
+    let f = |n: u32| {
+        consume(x);
+        let r = &y;
+    };
+}"#,
+        );
+        let config = InlayHintsConfig {
+            type_hints: false,
+            parameter_hints: false,
+            chaining_hints: false,
+            closure_capture_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 115..123,
+                kind: TypeHint,
+                label: "move: x; ref: y",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn closure_capture_hints_for_method_receivers() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[lang = "copy"]
+trait Copy {}
+struct S;
+impl S {
+    fn into_inner(self) -> u32 { 0 }
+    fn get(&self) -> u32 { 0 }
+    fn set(&mut self) {}
+}
+fn main() {
+    let a = S;
+    let b = S;
+    let mut c = S;
+    let f = || {
+        a.into_inner();
+        b.get();
+        c.set();
+    };
+}"#,
+        );
+        let config = InlayHintsConfig {
+            type_hints: false,
+            parameter_hints: false,
+            chaining_hints: false,
+            closure_capture_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 218..220,
+                kind: TypeHint,
+                label: "move: a; ref: b, c",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn no_closure_capture_hints_without_copy_lang_item() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+fn consume(s: S) {}
+fn main() {
+    let x = S;
+    let f = || consume(x);
+}"#,
+        );
+        let config = InlayHintsConfig {
+            type_hints: false,
+            parameter_hints: false,
+            chaining_hints: false,
+            closure_capture_hints: true,
+            ..Default::default()
+        };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"[]"###);
+    }
+
+    #[test]
+    fn config_with_kinds() {
+        let config = InlayHintsConfig::with_kinds(
+            HintKinds::TYPE | HintKinds::CHAINING | HintKinds::SELF_TYPE,
+        );
+        assert_eq!(
+            config,
+            InlayHintsConfig {
+                type_hints: true,
+                parameter_hints: false,
+                chaining_hints: true,
+                self_type_hint: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            InlayHintsConfig::with_kinds(HintKinds::EMPTY),
+            InlayHintsConfig {
+                type_hints: false,
+                parameter_hints: false,
+                chaining_hints: false,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn merges_overlapping_hints() {
+        let hint = |start: u32, kind, label: &str| InlayHint {
+            range: TextRange::at(start.into(), 5.into()),
+            kind,
+            label: label.into(),
+        };
+        let hints = vec![
+            hint(0, InlayKind::TypeHint, "i32"),
+            hint(0, InlayKind::ParameterHint, "x"),
+            hint(10, InlayKind::ParameterHint, "y"),
+        ];
+        assert_eq!(
+            merge_overlapping_hints(hints),
+            vec![
+                hint(0, InlayKind::ParameterHint, "x: i32"),
+                hint(10, InlayKind::ParameterHint, "y")
+            ]
+        );
+    }
+
+    #[test]
+    fn normalize_projection_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+trait Foo { type Bar; }
+struct S;
+impl Foo for S { type Bar = u32; }
+trait Tr<T> {}
+fn f() -> impl Tr<<S as Foo>::Bar> { loop {} }
+fn main() {
+    let x = f();
+}"#,
+        );
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 151..152,
+                kind: TypeHint,
+                label: "impl Tr<<S as Foo>::Bar>",
+            },
+        ]
+        "###);
+        let config = InlayHintsConfig { normalize_projection_hints: true, ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 151..152,
+                kind: TypeHint,
+                label: "impl Tr<u32>",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn max_hints_per_line() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn main() {
+    let (a, b, c, d) = (1, 2, 3, 4);
+}"#,
+        );
+        assert_eq!(analysis.inlay_hints(file_id, &InlayHintsConfig::default()).unwrap().len(), 4);
+        let config = InlayHintsConfig { max_hints_per_line: Some(2), ..Default::default() };
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, &config).unwrap(), @r###"
+        [
+            InlayHint {
+                range: 21..22,
+                kind: TypeHint,
+                label: "i32",
+            },
+            InlayHint {
+                range: 24..25,
+                kind: TypeHint,
+                label: "i32",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn custom_type_formatter() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Foo;
+fn foo(value: i32) -> Foo { Foo }
+fn main() {
+    let x = foo(1);
+}"#,
+        );
+        let hints = analysis
+            .inlay_hints_with_formatter(file_id, &InlayHintsConfig::default(), |db, ty| {
+                ty.display(db).to_string().to_uppercase()
+            })
+            .unwrap();
+        assert_debug_snapshot!(hints, @r###"
+        [
+            InlayHint {
+                range: 66..67,
+                kind: TypeHint,
+                label: "FOO",
+            },
+            InlayHint {
+                range: 74..75,
+                kind: ParameterHint,
+                label: "value",
+            },
+        ]
+        "###);
+    }
 }
@@ -67,7 +67,10 @@ pub use crate::{
     expand_macro::ExpandedMacro,
     folding_ranges::{Fold, FoldKind},
     hover::{HoverAction, HoverConfig, HoverGotoTypeData, HoverResult},
-    inlay_hints::{InlayHint, InlayHintsConfig, InlayKind},
+    inlay_hints::{
+        render_hints, HintKinds, HintVerbosity, InlayHint, InlayHintsConfig, InlayKind,
+        LabelDecoration, SmartPtrStyle,
+    },
     references::{Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult},
     runnables::{Runnable, RunnableKind, TestId},
     syntax_highlighting::{
@@ -75,7 +78,7 @@ pub use crate::{
     },
 };
 
-pub use hir::Documentation;
+pub use hir::{Documentation, MutabilityStyle};
 pub use ra_assists::{Assist, AssistConfig, AssistId, ResolvedAssist};
 pub use ra_db::{
     Canceled, CrateGraph, CrateId, Edition, FileId, FilePosition, FileRange, SourceRoot,
@@ -340,6 +343,41 @@ impl Analysis {
         self.with_db(|db| inlay_hints::inlay_hints(db, file_id, config))
     }
 
+    /// Like `inlay_hints`, but only keeps the hints for which `post` returns `true`.
+    /// `post` is called on each hint as it is collected, before overlapping
+    /// hints are merged and labels are decorated.
+    pub fn inlay_hints_with(
+        &self,
+        file_id: FileId,
+        config: &InlayHintsConfig,
+        post: impl FnMut(&InlayHint) -> bool + std::panic::UnwindSafe,
+    ) -> Cancelable<Vec<InlayHint>> {
+        self.with_db(|db| inlay_hints::inlay_hints_with(db, file_id, config, post))
+    }
+
+    /// Like `inlay_hints`, but the labels of type and chaining hints are
+    /// produced by `fmt`.
+    pub fn inlay_hints_with_formatter(
+        &self,
+        file_id: FileId,
+        config: &InlayHintsConfig,
+        fmt: impl Fn(&RootDatabase, &hir::Type) -> String + std::panic::UnwindSafe,
+    ) -> Cancelable<Vec<InlayHint>> {
+        self.with_db(|db| {
+            inlay_hints::inlay_hints_with_formatter(db, file_id, config, |ty| fmt(db, ty))
+        })
+    }
+
+    /// Like `inlay_hints`, but only for the innermost function containing
+    /// `position`.
+    pub fn inlay_hints_for_function_at(
+        &self,
+        position: FilePosition,
+        config: &InlayHintsConfig,
+    ) -> Cancelable<Vec<InlayHint>> {
+        self.with_db(|db| inlay_hints::inlay_hints_for_function_at(db, position, config))
+    }
+
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId) -> Cancelable<Vec<Fold>> {
         self.with_db(|db| folding_ranges::folding_ranges(&db.parse(file_id).tree()))
@@ -158,6 +158,12 @@ pub(super) fn stmt(p: &mut Parser, with_semi: StmtWithSemi) {
             expressions::expr_with_attrs(p);
         }
 
+        // test let_else
+        // fn f() { let Some(x) = None else { return }; }
+        if p.eat(T![else]) {
+            block_expr(p);
+        }
+
         match with_semi {
             StmtWithSemi::Yes => {
                 p.expect(T![;]);
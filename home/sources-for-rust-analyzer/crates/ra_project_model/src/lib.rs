@@ -288,10 +288,13 @@ impl ProjectWorkspace {
                         if let (Some(&from), Some(&to)) =
                             (crates.get(&from_crate_id), crates.get(&to_crate_id))
                         {
-                            if crate_graph
-                                .add_dep(from, CrateName::new(&dep.name).unwrap(), to)
-                                .is_err()
-                            {
+                            let name = CrateName::new(&dep.name).unwrap();
+                            let res = if dep.is_proc_macro {
+                                crate_graph.add_proc_macro_dep(from, name, to)
+                            } else {
+                                crate_graph.add_dep(from, name, to)
+                            };
+                            if res.is_err() {
                                 log::error!(
                                     "cyclic dependency {:?} -> {:?}",
                                     from_crate_id,
@@ -348,6 +351,7 @@ impl ProjectWorkspace {
                     sysroot.proc_macro().and_then(|it| sysroot_crates.get(&it).copied());
 
                 let mut pkg_to_lib_crate = FxHashMap::default();
+                let mut proc_macro_lib_crates = FxHashSet::default();
                 let mut pkg_crates = FxHashMap::default();
 
                 // Add test cfg for non-sysroot crates
@@ -400,6 +404,9 @@ impl ProjectWorkspace {
                             if cargo[tgt].kind == TargetKind::Lib {
                                 lib_tgt = Some((crate_id, cargo[tgt].name.clone()));
                                 pkg_to_lib_crate.insert(pkg, crate_id);
+                                if cargo[tgt].is_proc_macro {
+                                    proc_macro_lib_crates.insert(crate_id);
+                                }
                             }
                             if cargo[tgt].is_proc_macro {
                                 if let Some(proc_macro) = libproc_macro {
@@ -481,10 +488,13 @@ impl ProjectWorkspace {
                     for dep in cargo[pkg].dependencies.iter() {
                         if let Some(&to) = pkg_to_lib_crate.get(&dep.pkg) {
                             for &from in pkg_crates.get(&pkg).into_iter().flatten() {
-                                if crate_graph
-                                    .add_dep(from, CrateName::new(&dep.name).unwrap(), to)
-                                    .is_err()
-                                {
+                                let name = CrateName::new(&dep.name).unwrap();
+                                let res = if proc_macro_lib_crates.contains(&to) {
+                                    crate_graph.add_proc_macro_dep(from, name, to)
+                                } else {
+                                    crate_graph.add_dep(from, name, to)
+                                };
+                                if res.is_err() {
                                     log::error!(
                                         "cyclic dependency {} -> {}",
                                         &cargo[pkg].name,
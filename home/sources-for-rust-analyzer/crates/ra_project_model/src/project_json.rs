@@ -51,6 +51,7 @@ impl ProjectJson {
                         .map(|dep_data| Dependency {
                             crate_id: CrateId(dep_data.krate as u32),
                             name: dep_data.name.into(),
+                            is_proc_macro: false,
                         })
                         .collect::<Vec<_>>(),
                     cfg: {
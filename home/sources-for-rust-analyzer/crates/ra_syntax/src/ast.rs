@@ -100,6 +100,24 @@ fn assert_ast_is_object_safe() {
     fn _f(_: &dyn AstNode, _: &dyn NameOwner) {}
 }
 
+#[test]
+fn test_let_else_branch() {
+    let file = SourceFile::parse(
+        r#"
+        fn f() {
+            let x = { 1 };
+            let Some(y) = { opt } else { return };
+        }
+        "#,
+    )
+    .ok()
+    .unwrap();
+    let mut lets = file.syntax().descendants().filter_map(LetStmt::cast);
+    assert!(lets.next().unwrap().else_branch().is_none());
+    let let_else = lets.next().unwrap();
+    assert_eq!("{ return }", let_else.else_branch().unwrap().syntax().text().to_string());
+}
+
 #[test]
 fn test_doc_comment_none() {
     let file = SourceFile::parse(
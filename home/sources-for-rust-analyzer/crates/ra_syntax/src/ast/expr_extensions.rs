@@ -2,7 +2,7 @@
 
 use crate::{
     ast::{self, support, AstChildren, AstNode},
-    SmolStr,
+    Direction, SmolStr,
     SyntaxKind::*,
     SyntaxToken, T,
 };
@@ -48,6 +48,16 @@ impl ast::IfExpr {
     }
 }
 
+impl ast::LetStmt {
+    /// The block after `else` in `let PAT = EXPR else { ... };`. This can't be
+    /// a plain child lookup, as the initializer may be a block as well.
+    pub fn else_branch(&self) -> Option<ast::BlockExpr> {
+        self.else_token()?
+            .siblings_with_tokens(Direction::Next)
+            .find_map(|it| it.into_node().and_then(ast::BlockExpr::cast))
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum PrefixOp {
     /// The `*` operator for dereferencing
@@ -2127,6 +2127,7 @@ impl ExprStmt {
 /// ❰ let bar: u64; ❱
 /// ❰ let baz = 42; ❱
 /// ❰ let bruh: bool = true; ❱
+/// ❰ let Some(qux) = opt else { return }; ❱
 /// ```
 ///
 /// [Reference](https://doc.rust-lang.org/reference/statements.html#let-statements)
@@ -2141,6 +2142,7 @@ impl LetStmt {
     pub fn pat(&self) -> Option<Pat> { support::child(&self.syntax) }
     pub fn eq_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![=]) }
     pub fn initializer(&self) -> Option<Expr> { support::child(&self.syntax) }
+    pub fn else_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![else]) }
     pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
 }
 /// Condition of `if` or `while` expression.
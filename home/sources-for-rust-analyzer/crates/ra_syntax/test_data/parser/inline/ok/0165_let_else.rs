@@ -0,0 +1 @@
+fn f() { let Some(x) = None else { return }; }
@@ -160,6 +160,7 @@ impl Config {
                 parameter_hints: true,
                 chaining_hints: true,
                 max_length: None,
+                ..InlayHintsConfig::default()
             },
             completion: CompletionConfig {
                 enable_postfix_completions: true,
@@ -1,6 +1,11 @@
 //! Defines `Fixture` -- a convenient way to describe the initial state of
 //! rust-analyzer database from a single string.
 
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
 use rustc_hash::FxHashMap;
 use stdx::{lines_with_ends, split_delim, trim_indent};
 
@@ -10,6 +15,8 @@ pub struct Fixture {
     pub text: String,
     pub krate: Option<String>,
     pub deps: Vec<String>,
+    /// The subset of `deps` marked as `dep(proc-macro)`.
+    pub proc_macro_deps: Vec<String>,
     pub cfg_atoms: Vec<String>,
     pub cfg_key_values: Vec<(String, String)>,
     pub edition: Option<String>,
@@ -46,6 +53,11 @@ impl Fixture {
 
             if line.starts_with("//-") {
                 let meta = Fixture::parse_meta_line(line);
+                assert!(
+                    res.iter().all(|it| it.path != meta.path),
+                    "duplicate fixture file path {}",
+                    meta.path
+                );
                 res.push(meta)
             } else if let Some(entry) = res.last_mut() {
                 entry.text.push_str(line);
@@ -55,7 +67,25 @@ impl Fixture {
         res
     }
 
-    //- /lib.rs crate:foo deps:bar,baz cfg:foo=a,bar=b env:OUTDIR=path/to,OTHER=foo
+    /// Returns `(crate, dependency)` name pairs declared by this entry's
+    /// `crate:` and `deps:` components.
+    pub fn crate_deps(&self) -> Vec<(String, String)> {
+        match &self.krate {
+            Some(krate) => self.deps.iter().map(|dep| (krate.clone(), dep.clone())).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the files of a parsed fixture as paths relative to the fixture
+    /// root, paired with their contents.
+    pub fn to_files(fixture: &[Fixture]) -> Vec<(PathBuf, String)> {
+        fixture
+            .iter()
+            .map(|entry| (PathBuf::from(entry.path.trim_start_matches('/')), entry.text.clone()))
+            .collect()
+    }
+
+    //- /lib.rs crate:foo deps:bar,baz(proc-macro) cfg:foo=a,bar=b env:OUTDIR=path/to,OTHER=foo
     fn parse_meta_line(meta: &str) -> Fixture {
         assert!(meta.starts_with("//-"));
         let meta = meta["//-".len()..].trim();
@@ -66,6 +96,7 @@ impl Fixture {
 
         let mut krate = None;
         let mut deps = Vec::new();
+        let mut proc_macro_deps = Vec::new();
         let mut edition = None;
         let mut cfg_atoms = Vec::new();
         let mut cfg_key_values = Vec::new();
@@ -74,7 +105,19 @@ impl Fixture {
             let (key, value) = split_delim(component, ':').unwrap();
             match key {
                 "crate" => krate = Some(value.to_string()),
-                "deps" => deps = value.split(',').map(|it| it.to_string()).collect(),
+                "deps" => {
+                    for dep in value.split(',') {
+                        let suffix = "(proc-macro)";
+                        let dep = if dep.ends_with(suffix) {
+                            let dep = &dep[..dep.len() - suffix.len()];
+                            proc_macro_deps.push(dep.to_string());
+                            dep
+                        } else {
+                            dep
+                        };
+                        deps.push(dep.to_string());
+                    }
+                }
                 "edition" => edition = Some(value.to_string()),
                 "cfg" => {
                     for entry in value.split(',') {
@@ -100,6 +143,7 @@ impl Fixture {
             text: String::new(),
             krate: krate,
             deps,
+            proc_macro_deps,
             cfg_atoms,
             cfg_key_values,
             edition,
@@ -108,6 +152,19 @@ impl Fixture {
     }
 }
 
+/// Materializes the files of a parsed fixture under `dir`, creating
+/// directories as needed.
+pub fn write_fixture_to(dir: &Path, fixture: &[Fixture]) -> io::Result<()> {
+    for (path, text) in Fixture::to_files(fixture) {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, text)?;
+    }
+    Ok(())
+}
+
 #[test]
 #[should_panic]
 fn parse_fixture_checks_further_indented_metadata() {
@@ -123,6 +180,19 @@ fn parse_fixture_checks_further_indented_metadata() {
     );
 }
 
+#[test]
+#[should_panic(expected = "duplicate fixture file path /foo.rs")]
+fn parse_fixture_rejects_duplicate_paths() {
+    Fixture::parse(
+        r"
+        //- /foo.rs
+        fn foo() {}
+        //- /foo.rs
+        fn bar() {}
+        ",
+    );
+}
+
 #[test]
 fn parse_fixture_gets_full_meta() {
     let parsed = Fixture::parse(
@@ -140,3 +210,51 @@ fn parse_fixture_gets_full_meta() {
     assert_eq!("/lib.rs", meta.path);
     assert_eq!(2, meta.env.len());
 }
+
+#[test]
+fn parse_fixture_crate_deps() {
+    let parsed = Fixture::parse(
+        r"
+    //- /main.rs crate:a deps:b
+    fn main() {}
+    //- /lib.rs crate:b
+    pub fn f() {}
+    ",
+    );
+    let deps = parsed.iter().flat_map(Fixture::crate_deps).collect::<Vec<_>>();
+    assert_eq!(deps, vec![("a".to_string(), "b".to_string())]);
+}
+
+#[test]
+fn parse_fixture_proc_macro_deps() {
+    let parsed = Fixture::parse(
+        r"
+    //- /main.rs crate:a deps:b,c(proc-macro)
+    fn main() {}
+    ",
+    );
+    let meta = &parsed[0];
+    assert_eq!(meta.deps, vec!["b".to_string(), "c".to_string()]);
+    assert_eq!(meta.proc_macro_deps, vec!["c".to_string()]);
+}
+
+#[test]
+fn write_fixture_to_round_trips() {
+    let fixture = Fixture::parse(
+        r#"
+//- /main.rs
+fn main() {}
+//- /foo/bar.rs
+struct Bar;
+"#,
+    );
+    let dir = std::env::temp_dir().join(format!("test_utils_fixture_{}", std::process::id()));
+    write_fixture_to(&dir, &fixture).unwrap();
+
+    let files = Fixture::to_files(&fixture);
+    assert_eq!(files.len(), 2);
+    for (path, text) in files {
+        assert_eq!(fs::read(dir.join(path)).unwrap(), text.into_bytes());
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
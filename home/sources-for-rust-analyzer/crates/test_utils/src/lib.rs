@@ -11,8 +11,13 @@ pub mod mark;
 mod fixture;
 
 use std::{
-    env, fs,
+    env,
+    ffi::OsString,
+    fmt, fs, panic,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
 };
 
 use serde_json::Value;
@@ -21,13 +26,14 @@ use text_size::{TextRange, TextSize};
 pub use difference::Changeset as __Changeset;
 pub use rustc_hash::FxHashMap;
 
-pub use crate::fixture::Fixture;
+pub use crate::fixture::{write_fixture_to, Fixture};
 
 pub const CURSOR_MARKER: &str = "<|>";
 
 /// Asserts that two strings are equal, otherwise displays a rich diff between them.
 ///
 /// The diff shows changes from the "original" left string to the "actual" right string.
+/// Colors are stripped from the diff when `ansi_colors_disabled()`.
 ///
 /// All arguments starting from and including the 3rd one are passed to
 /// `eprintln!()` macro in case of text inequality.
@@ -36,7 +42,41 @@ macro_rules! assert_eq_text {
     ($left:expr, $right:expr) => {
         assert_eq_text!($left, $right,)
     };
+    ($left:expr, $right:expr, $($tt:tt)*) => {
+        $crate::__assert_eq_text!($crate::ansi_colors_disabled(), $left, $right, $($tt)*)
+    };
+}
+
+/// Like `assert_eq_text!`, but never uses colors in the diff, for logs which
+/// don't render ANSI escape codes.
+#[macro_export]
+macro_rules! assert_eq_text_plain {
+    ($left:expr, $right:expr) => {
+        assert_eq_text_plain!($left, $right,)
+    };
+    ($left:expr, $right:expr, $($tt:tt)*) => {
+        $crate::__assert_eq_text!(true, $left, $right, $($tt)*)
+    };
+}
+
+/// Like `assert_eq_text!`, but treats `\` and `/` as the same character, so
+/// that texts containing file paths compare equal on every platform.
+#[macro_export]
+macro_rules! assert_eq_text_paths {
+    ($left:expr, $right:expr) => {
+        assert_eq_text_paths!($left, $right,)
+    };
     ($left:expr, $right:expr, $($tt:tt)*) => {{
+        let left = $crate::normalize_path_separators(&$left);
+        let right = $crate::normalize_path_separators(&$right);
+        $crate::assert_eq_text!(left.as_str(), right.as_str(), $($tt)*)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_eq_text {
+    ($plain:expr, $left:expr, $right:expr, $($tt:tt)*) => {{
         let left = $left;
         let right = $right;
         if left != right {
@@ -44,7 +84,11 @@ macro_rules! assert_eq_text {
                 eprintln!("Left:\n{:?}\n\nRight:\n{:?}\n\nWhitespace difference\n", left, right);
             } else {
                 let changeset = $crate::__Changeset::new(left, right, "\n");
-                eprintln!("Left:\n{}\n\nRight:\n{}\n\nDiff:\n{}\n", left, right, changeset);
+                let mut diff = changeset.to_string();
+                if $plain {
+                    diff = $crate::strip_ansi(&diff);
+                }
+                eprintln!("Left:\n{}\n\nRight:\n{}\n\nDiff:\n{}\n", left, right, diff);
             }
             eprintln!($($tt)*);
             panic!("text differs");
@@ -52,14 +96,267 @@ macro_rules! assert_eq_text {
     }};
 }
 
+/// Replaces backslashes (and escaped backslashes, as in JSON) with forward
+/// slashes.
+pub fn normalize_path_separators(s: &str) -> String {
+    s.replace(r"\\", "/").replace(r"\", "/")
+}
+
+#[test]
+fn assert_eq_text_paths_ignores_slash_direction() {
+    assert_eq_text_paths!("file: src\\foo\\bar.rs\n", "file: src/foo/bar.rs\n");
+}
+
+/// Returns `true` if diffs shouldn't be colored, because `NO_COLOR` is set or
+/// we are running on CI or in a dumb terminal.
+pub fn ansi_colors_disabled() -> bool {
+    env::var_os("NO_COLOR").is_some()
+        || env::var_os("CI").is_some()
+        || env::var("TERM").map_or(true, |term| term == "dumb")
+}
+
+/// Removes ANSI escape sequences (like the colors in diffs) from `s`.
+pub fn strip_ansi(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            res.push(c);
+            continue;
+        }
+        // CSI sequences are `ESC [`, parameters, and a final byte in `@..=~`;
+        // other escapes are `ESC` followed by a single character.
+        if chars.next() == Some('[') {
+            chars.find(|c| ('@'..='~').contains(c));
+        }
+    }
+    res
+}
+
+#[test]
+fn strip_ansi_removes_escape_sequences() {
+    assert_eq!(strip_ansi("\u{1b}[92mfoo\u{1b}[0m bar\u{1b}[1;31m!\u{1b}[0m"), "foo bar!");
+    assert_eq!(strip_ansi("no escapes: [0m"), "no escapes: [0m");
+}
+
+/// Asserts that two strings consist of the same lines, ignoring their order.
+///
+/// On failure, displays the lines which are only present on one side.
+#[macro_export]
+macro_rules! assert_eq_lines_unordered {
+    ($left:expr, $right:expr) => {{
+        if let Some(diff) = $crate::unordered_lines_diff($left, $right) {
+            eprintln!("{}", diff);
+            panic!("lines differ");
+        }
+    }};
+}
+
+/// Returns the lines only present in `left` and only present in `right`,
+/// treating both as multisets of lines, or `None` if there are none.
+pub fn unordered_lines_diff(left: &str, right: &str) -> Option<String> {
+    let mut left = left.split('\n').collect::<Vec<_>>();
+    let mut right = right.split('\n').collect::<Vec<_>>();
+    left.sort();
+    right.sort();
+    if left == right {
+        return None;
+    }
+
+    let mut only_left = Vec::new();
+    let mut only_right = Vec::new();
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    loop {
+        match (left.peek(), right.peek()) {
+            (Some(l), Some(r)) if l == r => {
+                left.next();
+                right.next();
+            }
+            (Some(l), Some(r)) if l < r => only_left.extend(left.next()),
+            (Some(_), Some(_)) | (None, Some(_)) => only_right.extend(right.next()),
+            (Some(_), None) => only_left.extend(left.next()),
+            (None, None) => break,
+        }
+    }
+    Some(format!(
+        "Only in left:\n{}\n\nOnly in right:\n{}\n",
+        only_left.join("\n"),
+        only_right.join("\n")
+    ))
+}
+
+#[test]
+fn assert_eq_lines_unordered_ignores_order() {
+    assert_eq_lines_unordered!("error: a\nwarning: b\nerror: c", "error: c\nerror: a\nwarning: b");
+}
+
+#[test]
+#[should_panic(expected = "lines differ")]
+fn assert_eq_lines_unordered_detects_extra_line() {
+    let left = "error: a\nwarning: b";
+    let right = "warning: b\nerror: a\nerror: extra";
+    assert_eq!(
+        unordered_lines_diff(left, right).unwrap(),
+        "Only in left:\n\n\nOnly in right:\nerror: extra\n"
+    );
+    assert_eq_lines_unordered!(left, right);
+}
+
+/// Asserts that two strings consist of the same tokens, ignoring whitespace,
+/// e.g. to compare generated code regardless of its formatting.
+///
+/// On failure, displays the first token which differs.
+#[macro_export]
+macro_rules! assert_eq_tokens {
+    ($left:expr, $right:expr) => {{
+        if let Some(diff) = $crate::tokens_diff($left, $right) {
+            eprintln!("{}", diff);
+            panic!("tokens differ");
+        }
+    }};
+}
+
+/// Describes the first token which differs between `left` and `right`, or
+/// returns `None` if they only differ in whitespace.
+///
+/// Tokens are runs of alphanumeric characters and underscores, or single
+/// punctuation characters, so whitespace inside string literals is ignored
+/// as well.
+pub fn tokens_diff(left: &str, right: &str) -> Option<String> {
+    let left = split_tokens(left);
+    let right = split_tokens(right);
+    let idx = left
+        .iter()
+        .zip(&right)
+        .position(|(l, r)| l != r)
+        .unwrap_or_else(|| left.len().min(right.len()));
+    if idx == left.len() && idx == right.len() {
+        return None;
+    }
+    let token = |tokens: &[&str]| tokens.get(idx).copied().unwrap_or("<end of input>");
+    Some(format!(
+        "First difference at token {}:\nleft:  {}\nright: {}\n",
+        idx,
+        token(&left),
+        token(&right)
+    ))
+}
+
+fn split_tokens(text: &str) -> Vec<&str> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut res = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        if is_word_char(c) {
+            while let Some(&(idx, c)) = chars.peek() {
+                if !is_word_char(c) {
+                    break;
+                }
+                end = idx + c.len_utf8();
+                chars.next();
+            }
+        }
+        res.push(&text[start..end]);
+    }
+    res
+}
+
+#[test]
+fn assert_eq_tokens_ignores_indentation() {
+    assert_eq_tokens!("fn foo() {\n    let x = 92;\n}", "fn foo()\n{\n        let x=92;\n}\n");
+}
+
+#[test]
+#[should_panic(expected = "tokens differ")]
+fn assert_eq_tokens_detects_different_identifier() {
+    let left = "fn foo() { let x = 92; }";
+    let right = "fn foo() {\n    let y = 92;\n}";
+    assert_eq!(
+        tokens_diff(left, right).unwrap(),
+        "First difference at token 6:\nleft:  x\nright: y\n"
+    );
+    assert_eq_tokens!(left, right);
+}
+
+/// Asserts that two slices are equal, otherwise displays a line-wise diff of
+/// their elements, each formatted with `{:?}` on its own line.
+pub fn assert_debug_eq_slices<T: fmt::Debug>(expected: &[T], actual: &[T]) {
+    let render = |items: &[T]| items.iter().map(|it| format!("{:?}\n", it)).collect::<String>();
+    assert_eq_text!(&*render(expected), &*render(actual));
+}
+
+#[test]
+fn assert_debug_eq_slices_diffs_elements() {
+    let expected = vec![1, 2, 3];
+    assert_debug_eq_slices(&expected, &vec![1, 2, 3]);
+    assert_panics_with("text differs", || assert_debug_eq_slices(&expected, &vec![1, 4, 3]));
+}
+
+/// Generates a `#[test]` function per `(name, input, expected)` row, which
+/// calls `check(input, expected)`.
+///
+/// ```ignore
+/// fixture_table_tests! {
+///     check: check_hover,
+///     (hover_on_struct, "struct S<|>;", "S"),
+///     (hover_on_fn, "fn f<|>() {}", "fn f()"),
+/// }
+/// ```
+#[macro_export]
+macro_rules! fixture_table_tests {
+    (check: $check:expr, $(($name:ident, $input:expr, $expected:expr)),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                let check = $check;
+                check($input, $expected);
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+fixture_table_tests! {
+    check: |input: &str, expected: usize| assert_eq!(input.split_whitespace().count(), expected),
+    (fixture_table_tests_first_row, "fn main() {}", 3),
+    (fixture_table_tests_second_row, "struct S;", 2),
+}
+
 /// Infallible version of `try_extract_offset()`.
 pub fn extract_offset(text: &str) -> (TextSize, String) {
     match try_extract_offset(text) {
-        None => panic!("text should contain cursor marker"),
+        None => match find_cursor_marker_typo(text) {
+            Some((offset, typo)) => panic!(
+                "text should contain cursor marker, did you mean `{}`? found `{}` at offset {}",
+                CURSOR_MARKER, typo, offset
+            ),
+            None => panic!("text should contain cursor marker"),
+        },
         Some(result) => result,
     }
 }
 
+/// Finds the first sequence in `text` which looks like a mistyped `<|>`.
+fn find_cursor_marker_typo(text: &str) -> Option<(usize, &'static str)> {
+    // Longer typos go first, so that `<||>` isn't reported as `<|`.
+    const TYPOS: &[&str] = &["<||>", "<|", "|>"];
+    TYPOS
+        .iter()
+        .filter_map(|&typo| text.find(typo).map(|offset| (offset, typo)))
+        .min_by_key(|&(offset, _)| offset)
+}
+
+#[test]
+#[should_panic(expected = "did you mean `<|>`? found `<|` at offset 6")]
+fn extract_offset_reports_marker_typo() {
+    extract_offset("fn foo<|() {}");
+}
+
 /// Returns the offset of the first occurence of `<|>` marker and the copy of `text`
 /// without the marker.
 fn try_extract_offset(text: &str) -> Option<(TextSize, String)> {
@@ -87,12 +384,25 @@ fn try_extract_range(text: &str) -> Option<(TextRange, String)> {
     Some((TextRange::new(start, end), text))
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RangeOrOffset {
     Range(TextRange),
     Offset(TextSize),
 }
 
+impl RangeOrOffset {
+    /// Inserts the `<|>` marker(s) for `self` into `text`; the inverse of
+    /// `extract_range_or_offset`.
+    pub fn render(&self, text: &str) -> String {
+        match *self {
+            RangeOrOffset::Range(range) => {
+                add_cursor(&add_cursor(text, range.end()), range.start())
+            }
+            RangeOrOffset::Offset(offset) => add_cursor(text, offset),
+        }
+    }
+}
+
 impl From<RangeOrOffset> for TextRange {
     fn from(selection: RangeOrOffset) -> Self {
         match selection {
@@ -115,6 +425,43 @@ pub fn extract_range_or_offset(text: &str) -> (RangeOrOffset, String) {
     (RangeOrOffset::Offset(offset), text)
 }
 
+/// Like `extract_range_or_offset()`, but also returns the number of `<|>`
+/// markers left over in the returned text, so that tests can reject fixtures
+/// with too many markers.
+pub fn extract_range_or_offset_checked(text: &str) -> (RangeOrOffset, String, usize) {
+    let (range_or_offset, text) = extract_range_or_offset(text);
+    let leftover = text.matches(CURSOR_MARKER).count();
+    (range_or_offset, text, leftover)
+}
+
+#[test]
+fn extract_range_or_offset_checked_counts_leftover_markers() {
+    let range = RangeOrOffset::Range(TextRange::new(4.into(), 7.into()));
+
+    let (range_or_offset, text, leftover) = extract_range_or_offset_checked("foo <|>bar<|> baz");
+    assert_eq!((range_or_offset, text.as_str(), leftover), (range, "foo bar baz", 0));
+
+    let (range_or_offset, text, leftover) = extract_range_or_offset_checked("foo <|>bar<|> <|>baz");
+    assert_eq!((range_or_offset, text.as_str(), leftover), (range, "foo bar <|>baz", 1));
+}
+
+/// Like `extract_range_or_offset()`, but also returns the clean text with the
+/// marker(s) rendered back, which should be equal to `text`.
+pub fn extract_and_rerender(text: &str) -> (RangeOrOffset, String, String) {
+    let (range_or_offset, text) = extract_range_or_offset(text);
+    let rendered = range_or_offset.render(&text);
+    (range_or_offset, text, rendered)
+}
+
+#[test]
+fn extract_and_rerender_round_trips() {
+    for fixture in &["foo <|>bar baz", "foo <|>bar<|> baz"] {
+        let (_, text, rendered) = extract_and_rerender(fixture);
+        assert_eq!(text, "foo bar baz");
+        assert_eq!(&rendered, fixture);
+    }
+}
+
 /// Extracts ranges, marked with `<tag> </tag>` pairs from the `text`
 pub fn extract_ranges(mut text: &str, tag: &str) -> (Vec<TextRange>, String) {
     let open = format!("<{}>", tag);
@@ -159,6 +506,94 @@ pub fn add_cursor(text: &str, offset: TextSize) -> String {
     res
 }
 
+/// Describes `range` of `source` for humans, as in `line 3, col 5..9: "foo()"`.
+///
+/// Lines and columns are 1-based, columns count chars rather than bytes. A
+/// range spanning several lines is described as `line 3, col 5..line 4, col 2`.
+pub fn describe_range(source: &str, range: TextRange) -> String {
+    let (start_line, start_col) = line_col_of(source, range.start());
+    let (end_line, end_col) = line_col_of(source, range.end());
+    let text = &source[range];
+    if start_line == end_line {
+        format!("line {}, col {}..{}: {:?}", start_line, start_col, end_col, text)
+    } else {
+        format!(
+            "line {}, col {}..line {}, col {}: {:?}",
+            start_line, start_col, end_line, end_col, text
+        )
+    }
+}
+
+/// 1-based line and char column of `offset`.
+fn line_col_of(text: &str, offset: TextSize) -> (usize, usize) {
+    let before = &text[..usize::from(offset)];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+#[test]
+fn describe_range_counts_lines_and_chars() {
+    let source = "fn main() {\n    let ä = foo();\n}\n";
+    let start = source.find("foo").unwrap() as u32;
+    let range = TextRange::at(start.into(), 5.into());
+    assert_eq!(describe_range(source, range), r#"line 2, col 13..18: "foo()""#);
+
+    let start = source.find('{').unwrap() as u32;
+    let range = TextRange::at(start.into(), 8.into());
+    assert_eq!(describe_range(source, range), r#"line 1, col 11..line 2, col 7: "{\n    le""#);
+}
+
+/// Reduces a failing fixture to a minimal reproduction.
+///
+/// The `text` is split into blocks separated by blank lines (usually, top-level
+/// items), and blocks are removed one by one for as long as `still_fails` keeps
+/// returning `true` for the remaining text.
+///
+/// # Panics
+/// Panics if `still_fails` returns `false` for the original `text`.
+pub fn bisect_fixture(text: &str, still_fails: impl Fn(&str) -> bool) -> String {
+    let mut blocks = text
+        .split("\n\n")
+        .map(|it| it.trim_matches('\n'))
+        .filter(|it| !it.trim().is_empty())
+        .collect::<Vec<_>>();
+    assert!(still_fails(&blocks.join("\n\n")), "fixture should fail before bisecting");
+    loop {
+        let n_blocks = blocks.len();
+        let mut i = 0;
+        while i < blocks.len() {
+            let mut candidate = blocks.clone();
+            candidate.remove(i);
+            if still_fails(&candidate.join("\n\n")) {
+                blocks = candidate;
+            } else {
+                i += 1;
+            }
+        }
+        if blocks.len() == n_blocks {
+            break;
+        }
+    }
+    blocks.join("\n\n")
+}
+
+#[test]
+fn bisect_fixture_keeps_only_failing_item() {
+    let fixture = "
+struct A;
+
+fn foo() {
+    bar();
+}
+
+struct B;
+
+impl B {}
+";
+    let reduced = bisect_fixture(fixture, |text| text.contains("bar()"));
+    assert_eq!(reduced, "fn foo() {\n    bar();\n}");
+}
+
 // Comparison functionality borrowed from cargo:
 
 /// Compare a line with an expected pattern.
@@ -166,10 +601,8 @@ pub fn add_cursor(text: &str, offset: TextSize) -> String {
 ///   (similar to `.*` in a regex).
 pub fn lines_match(expected: &str, actual: &str) -> bool {
     // Let's not deal with / vs \ (windows...)
-    // First replace backslash-escaped backslashes with forward slashes
-    // which can occur in, for example, JSON output
-    let expected = expected.replace(r"\\", "/").replace(r"\", "/");
-    let mut actual: &str = &actual.replace(r"\\", "/").replace(r"\", "/");
+    let expected = normalize_path_separators(expected);
+    let mut actual: &str = &normalize_path_separators(actual);
     for (i, part) in expected.split("[..]").enumerate() {
         match actual.find(part) {
             Some(j) => {
@@ -197,10 +630,39 @@ fn lines_match_works() {
     assert!(!lines_match("b", "cb"));
 }
 
+/// Runs `f` and asserts that it panics with a message matching `pattern`,
+/// which may contain `[..]` wildcards as in `lines_match`.
+pub fn assert_panics_with(pattern: &str, f: impl FnOnce()) {
+    let payload = match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(()) => panic!("expected a panic matching `{}`", pattern),
+        Err(payload) => payload,
+    };
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("<non-string panic payload>");
+    assert!(
+        lines_match(pattern, message),
+        "panic message `{}` doesn't match `{}`",
+        message,
+        pattern
+    );
+}
+
+#[test]
+fn assert_panics_with_matches_message() {
+    assert_panics_with("[..]cursor marker[..]", || {
+        extract_offset("no marker");
+    });
+}
+
 /// Compares JSON object for approximate equality.
 /// You can use `[..]` wildcard in strings (useful for OS dependent things such
 /// as paths). You can use a `"{...}"` string literal as a wildcard for
-/// arbitrary nested JSON. Arrays are sorted before comparison.
+/// arbitrary nested JSON, `["{...}"]` for an array of any length, and
+/// `[template, "..."]` for a non-empty array whose first element matches
+/// `template`. Arrays are sorted before comparison.
 pub fn find_mismatch<'a>(expected: &'a Value, actual: &'a Value) -> Option<(&'a Value, &'a Value)> {
     use serde_json::Value::*;
     match (expected, actual) {
@@ -208,6 +670,19 @@ pub fn find_mismatch<'a>(expected: &'a Value, actual: &'a Value) -> Option<(&'a
         (&Bool(l), &Bool(r)) if l == r => None,
         (&String(ref l), &String(ref r)) if lines_match(l, r) => None,
         (&Array(ref l), &Array(ref r)) => {
+            match l.as_slice() {
+                // `["{...}"]` matches an array of any length
+                [String(wildcard)] if wildcard == "{...}" => return None,
+                // `[template, "..."]` only checks the first element
+                [template, String(rest)] if rest == "..." => {
+                    return match r.first() {
+                        Some(first) => find_mismatch(template, first),
+                        None => Some((expected, actual)),
+                    };
+                }
+                _ => (),
+            }
+
             if l.len() != r.len() {
                 return Some((expected, actual));
             }
@@ -246,6 +721,51 @@ pub fn find_mismatch<'a>(expected: &'a Value, actual: &'a Value) -> Option<(&'a
     }
 }
 
+#[test]
+fn find_mismatch_array_wildcards() {
+    use serde_json::json;
+
+    let any = json!(["{...}"]);
+    assert!(find_mismatch(&any, &json!([])).is_none());
+    assert!(find_mismatch(&any, &json!([1, "two", { "three": 3 }])).is_none());
+
+    let first = json!([{ "kind": "error" }, "..."]);
+    assert!(find_mismatch(&first, &json!([{ "kind": "error" }, 2, 3])).is_none());
+    assert!(find_mismatch(&first, &json!([{ "kind": "warning" }, 2])).is_some());
+    assert!(find_mismatch(&first, &json!([])).is_some());
+}
+
+/// Serializes `range` as a `{ "start": .., "end": .. }` JSON object, so that
+/// expected ranges can be stored on disk and compared with `find_mismatch`.
+pub fn range_to_json(range: TextRange) -> Value {
+    serde_json::json!({
+        "start": u32::from(range.start()),
+        "end": u32::from(range.end()),
+    })
+}
+
+/// Inverse of `range_to_json`.
+///
+/// # Panics
+/// Panics if `value` is not an object with integer `start` and `end` fields.
+pub fn range_from_json(value: &Value) -> TextRange {
+    let offset = |key: &str| {
+        let offset = value[key]
+            .as_u64()
+            .unwrap_or_else(|| panic!("range should have an integer `{}`: {}", key, value));
+        TextSize::from(offset as u32)
+    };
+    TextRange::new(offset("start"), offset("end"))
+}
+
+#[test]
+fn range_json_round_trip() {
+    let range = TextRange::new(TextSize::from(3), TextSize::from(92));
+    let json = range_to_json(range);
+    assert!(find_mismatch(&serde_json::json!({ "start": 3, "end": 92 }), &json).is_none());
+    assert_eq!(range_from_json(&json), range);
+}
+
 /// Calls callback `f` with input code and file paths for each `.rs` file in `test_data_dir`
 /// subdirectories defined by `paths`.
 ///
@@ -261,26 +781,238 @@ where
     for (path, input_code) in collect_rust_files(test_data_dir, paths) {
         let actual = f(&input_code, &path);
         let path = path.with_extension(outfile_extension);
+        assert_expected_output(&path, &input_code, &actual);
+    }
+}
+
+/// Like `dir_tests`, but `f` produces labeled ranges of the input code, which
+/// are compared against the `.txt` file holding the input annotated with
+/// `render_ranges`.
+pub fn dir_tests_ranges<F>(test_data_dir: &Path, paths: &[&str], f: F)
+where
+    F: Fn(&str) -> Vec<(TextRange, String)>,
+{
+    for (path, input_code) in collect_rust_files(test_data_dir, paths) {
+        let actual = render_ranges(&input_code, &f(&input_code));
+        let path = path.with_extension("txt");
+        assert_expected_output(&path, &input_code, &actual);
+    }
+}
+
+/// Like `dir_tests`, but `f` produces JSON, which is compared against the
+/// `.json` file with `find_mismatch`, so that file may use its wildcards.
+pub fn dir_json_tests<F>(test_data_dir: &Path, paths: &[&str], f: F)
+where
+    F: Fn(&str, &Path) -> Value,
+{
+    for (path, input_code) in collect_rust_files(test_data_dir, paths) {
+        let actual = f(&input_code, &path);
+        let path = path.with_extension("json");
         if !path.exists() {
-            println!("\nfile: {}", path.display());
-            println!("No .txt file with expected result, creating...\n");
-            println!("{}\n{}", input_code, actual);
-            fs::write(&path, &actual).unwrap();
-            panic!("No expected result");
+            let actual = serde_json::to_string_pretty(&actual).unwrap();
+            assert_expected_output(&path, &input_code, &actual);
+        }
+        let expected = read_json(&path);
+        if let Some((expected_part, actual_part)) = find_mismatch(&expected, &actual) {
+            panic!(
+                "{} doesn't match.\nExpected:\n{}\nActual:\n{}\nMismatch:\n{}\n!=\n{}",
+                path.display(),
+                serde_json::to_string_pretty(&expected).unwrap(),
+                serde_json::to_string_pretty(&actual).unwrap(),
+                serde_json::to_string_pretty(expected_part).unwrap(),
+                serde_json::to_string_pretty(actual_part).unwrap(),
+            );
         }
-        let expected = read_text(&path);
-        assert_equal_text(&expected, &actual, &path);
     }
 }
 
+/// Reads and parses the JSON file at `path`.
+pub fn read_json(path: &Path) -> Value {
+    let text = read_text(path);
+    serde_json::from_str(&text)
+        .unwrap_or_else(|err| panic!("invalid JSON in {}: {}", path.display(), err))
+}
+
+/// Annotates `text` with labeled ranges, each rendered as
+/// `<|>covered text<|>/* label */`.
+pub fn render_ranges(text: &str, ranges: &[(TextRange, String)]) -> String {
+    // Ends go before starts at the same offset, so that adjacent ranges don't
+    // nest. An empty range is inserted as a whole.
+    let mut inserts = Vec::new();
+    for (range, label) in ranges {
+        let end = format!("{}/* {} */", CURSOR_MARKER, label);
+        if range.is_empty() {
+            inserts.push((range.start(), 1, format!("{}{}", CURSOR_MARKER, end)));
+        } else {
+            inserts.push((range.start(), 1, CURSOR_MARKER.to_string()));
+            inserts.push((range.end(), 0, end));
+        }
+    }
+    inserts.sort_by_key(|&(offset, order, _)| (offset, order));
+
+    let mut res = String::new();
+    let mut last = 0;
+    for (offset, _, insert) in inserts {
+        let offset = usize::from(offset);
+        res.push_str(&text[last..offset]);
+        res.push_str(&insert);
+        last = offset;
+    }
+    res.push_str(&text[last..]);
+    res
+}
+
+/// Asserts that `actual` ranges are equal to `expected`, showing the ranges
+/// as `<|>`-marked copies of `source` on mismatch.
+pub fn assert_ranges_eq(source: &str, expected: &[TextRange], actual: &[TextRange]) {
+    if expected == actual {
+        return;
+    }
+    assert_eq_text!(&*mark_ranges(source, expected), &*mark_ranges(source, actual));
+    // Same ranges, different order.
+    assert_eq!(expected, actual);
+}
+
+/// Wraps each of `ranges` in `text` into a pair of `<|>` markers.
+fn mark_ranges(text: &str, ranges: &[TextRange]) -> String {
+    let mut inserts = Vec::new();
+    for range in ranges {
+        inserts.push((range.start(), 1));
+        inserts.push((range.end(), 0));
+    }
+    inserts.sort();
+
+    let mut res = String::new();
+    let mut last = 0;
+    for (offset, _) in inserts {
+        let offset = usize::from(offset);
+        res.push_str(&text[last..offset]);
+        res.push_str(CURSOR_MARKER);
+        last = offset;
+    }
+    res.push_str(&text[last..]);
+    res
+}
+
+#[test]
+fn assert_ranges_eq_marks_ranges_in_source() {
+    let source = "fn foo() {}";
+    let expected = [TextRange::new(3.into(), 6.into())];
+    let actual = [TextRange::new(3.into(), 5.into())];
+    assert_eq!(mark_ranges(source, &expected), "fn <|>foo<|>() {}");
+    assert_eq!(mark_ranges(source, &actual), "fn <|>fo<|>o() {}");
+    assert_panics_with("text differs", || assert_ranges_eq(source, &expected, &actual));
+    assert_ranges_eq(source, &expected, &expected);
+}
+
+#[test]
+fn dir_tests_ranges_corpus() {
+    let test_data_dir = project_dir().join("crates/test_utils/test_data");
+    dir_tests_ranges(&test_data_dir, &["ranges"], |text| {
+        let mut res = Vec::new();
+        for (keyword, label) in &[("fn ", "keyword"), ("let ", "binding")] {
+            for (offset, _) in text.match_indices(keyword) {
+                let range = if *label == "keyword" {
+                    TextRange::at(TextSize::from(offset as u32), TextSize::from(2))
+                } else {
+                    let start = offset + keyword.len();
+                    let len = text[start..].find(' ').unwrap();
+                    TextRange::at(TextSize::from(start as u32), TextSize::from(len as u32))
+                };
+                res.push((range, label.to_string()));
+            }
+        }
+        res
+    });
+}
+
+#[test]
+fn dir_json_tests_corpus() {
+    use serde_json::json;
+
+    let test_data_dir = project_dir().join("crates/test_utils/test_data");
+    dir_json_tests(&test_data_dir, &["json"], |text, path| {
+        let names = |keyword: &str| {
+            text.lines()
+                .filter(|line| line.starts_with(keyword))
+                .map(|line| &line[keyword.len()..])
+                .map(|rest| rest.split(|c: char| !c.is_alphanumeric()).next().unwrap())
+                .collect::<Vec<_>>()
+        };
+        json!({
+            "fns": names("fn "),
+            "structs": names("struct "),
+            "path": path.display().to_string(),
+        })
+    });
+}
+
+/// Generates a `#[test]` function `name` which compares `f(input)` with the
+/// golden file `crates/<crate>/test_data/golden/<name>.txt`.
+///
+/// As with `dir_tests`, a missing golden file is created and filled with the
+/// output, but the test fails.
+///
+/// ```ignore
+/// golden_test!(lex_hello, "fn hello() {}", |code: &str| lex_to_string(code));
+/// ```
+#[macro_export]
+macro_rules! golden_test {
+    ($name:ident, $input:expr, $f:expr) => {
+        #[test]
+        fn $name() {
+            let dir = $crate::golden_dir(env!("CARGO_PKG_NAME"));
+            $crate::check_golden(&dir, stringify!($name), $input, $f);
+        }
+    };
+}
+
+/// The directory holding the golden files of `golden_test!`s in `crate_name`.
+pub fn golden_dir(crate_name: &str) -> PathBuf {
+    project_dir().join("crates").join(crate_name).join("test_data/golden")
+}
+
+/// Compares `f(input)` with the golden file `<name>.txt` in `dir`, creating it
+/// (and failing) if it doesn't exist yet.
+pub fn check_golden(dir: &Path, name: &str, input: &str, f: impl FnOnce(&str) -> String) {
+    let actual = f(input);
+    fs::create_dir_all(dir).unwrap();
+    assert_expected_output(&dir.join(format!("{}.txt", name)), input, &actual);
+}
+
+golden_test!(golden_test_uppercases_input, "fn main() {}\n", |code: &str| code.to_uppercase());
+
+/// Compares `actual` with the contents of the expected output file at `path`.
+///
+/// If there is no such file it will be created and filled with `actual`, but
+/// the test will fail.
+pub fn assert_expected_output(path: &Path, input_code: &str, actual: &str) {
+    if !path.exists() {
+        println!("\nfile: {}", path.display());
+        println!("No .txt file with expected result, creating...\n");
+        println!("{}\n{}", input_code, actual);
+        fs::write(path, actual).unwrap();
+        panic!("No expected result");
+    }
+    let expected = read_text(path);
+    assert_equal_text(&expected, actual, path);
+}
+
 /// Collects all `.rs` files from `dir` subdirectories defined by `paths`.
+///
+/// Path components containing `*` are expanded against the filesystem, so
+/// `"ok/*"` stands for every immediate subdirectory of `ok`.
 pub fn collect_rust_files(root_dir: &Path, paths: &[&str]) -> Vec<(PathBuf, String)> {
     paths
         .iter()
         .flat_map(|path| {
-            let path = root_dir.to_owned().join(path);
-            rust_files_in_dir(&path).into_iter()
+            if path.contains('*') {
+                expand_dir_glob(root_dir, path)
+            } else {
+                vec![root_dir.to_owned().join(path)]
+            }
         })
+        .flat_map(|path| rust_files_in_dir(&path).into_iter())
         .map(|path| {
             let text = read_text(&path);
             (path, text)
@@ -288,6 +1020,58 @@ pub fn collect_rust_files(root_dir: &Path, paths: &[&str]) -> Vec<(PathBuf, Stri
         .collect()
 }
 
+/// Expands a `/`-separated `pattern` relative to `root_dir` into the sorted
+/// list of directories it matches.
+fn expand_dir_glob(root_dir: &Path, pattern: &str) -> Vec<PathBuf> {
+    let mut acc = vec![root_dir.to_owned()];
+    for component in pattern.split('/').filter(|it| !it.is_empty()) {
+        if !component.contains('*') {
+            acc.iter_mut().for_each(|dir| dir.push(component));
+            continue;
+        }
+        let mut next = Vec::new();
+        for dir in &acc {
+            let entries = match fs::read_dir(dir) {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            for entry in entries {
+                let path = entry.unwrap().path();
+                let matches = path
+                    .file_name()
+                    .and_then(|it| it.to_str())
+                    .map_or(false, |name| glob_matches(component, name));
+                if matches && path.is_dir() {
+                    next.push(path);
+                }
+            }
+        }
+        next.sort();
+        acc = next;
+    }
+    acc
+}
+
+/// Matches `name` against `pattern`, where `*` stands for any (possibly empty)
+/// sequence of characters.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    if !name.starts_with(first) {
+        return false;
+    }
+    let mut rest = &name[first.len()..];
+    let mut parts = parts.collect::<Vec<_>>();
+    let last = parts.pop().unwrap_or_default();
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
 /// Collects paths to all `.rs` files from `dir` in a sorted `Vec<PathBuf>`.
 fn rust_files_in_dir(dir: &Path) -> Vec<PathBuf> {
     let mut acc = Vec::new();
@@ -302,6 +1086,123 @@ fn rust_files_in_dir(dir: &Path) -> Vec<PathBuf> {
     acc
 }
 
+#[test]
+fn collect_rust_files_expands_globs() {
+    let root = env::temp_dir().join(format!("test_utils_dir_glob_{}", std::process::id()));
+    for (dir, file) in &[("ok/first", "a.rs"), ("ok/second", "b.rs"), ("err/third", "c.rs")] {
+        let dir = root.join(dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(file), "fn main() {}\n").unwrap();
+    }
+    fs::write(root.join("ok/not_a_dir.rs"), "").unwrap();
+
+    let files = collect_rust_files(&root, &["ok/*"]);
+    let names = files
+        .iter()
+        .map(|(path, _)| path.strip_prefix(&root).unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            Path::new("ok").join("first").join("a.rs"),
+            Path::new("ok").join("second").join("b.rs")
+        ]
+    );
+    assert_eq!(collect_rust_files(&root, &["ok/first"]).len(), 1);
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+/// Asserts that the `.rs` and `.txt` files in `actual_dir` match those in
+/// `expected_dir`, reporting files present in only one of them and diffing
+/// the contents of the rest.
+///
+/// With `UPDATE_EXPECTATIONS` set, `expected_dir` is instead rewritten to
+/// mirror `actual_dir`.
+pub fn assert_dirs_eq(expected_dir: &Path, actual_dir: &Path) {
+    let expected = expectation_files_in_dir(expected_dir);
+    let actual = expectation_files_in_dir(actual_dir);
+
+    if env::var("UPDATE_EXPECTATIONS").is_ok() {
+        fs::create_dir_all(expected_dir).unwrap();
+        for name in expected.iter().filter(|it| !actual.contains(it)) {
+            println!("removing {}", expected_dir.join(name).display());
+            fs::remove_file(expected_dir.join(name)).unwrap();
+        }
+        for name in &actual {
+            let text = read_text(&actual_dir.join(name));
+            let path = expected_dir.join(name);
+            if !path.exists() || read_text(&path) != text {
+                println!("rewriting {}", path.display());
+                rewrite_expectation(&path, &text);
+            }
+        }
+        return;
+    }
+
+    let missing = expected.iter().filter(|it| !actual.contains(it)).collect::<Vec<_>>();
+    let unexpected = actual.iter().filter(|it| !expected.contains(it)).collect::<Vec<_>>();
+    if !missing.is_empty() || !unexpected.is_empty() {
+        panic!(
+            "directories differ\nmissing from {}: {:?}\nunexpected in {}: {:?}",
+            actual_dir.display(),
+            missing,
+            actual_dir.display(),
+            unexpected
+        );
+    }
+    for name in &expected {
+        let expected_text = read_text(&expected_dir.join(name));
+        let actual_text = read_text(&actual_dir.join(name));
+        assert_eq_text!(&*expected_text, &*actual_text, "file: {}", name);
+    }
+}
+
+/// Collects the sorted names of all `.rs` and `.txt` files directly in `dir`.
+fn expectation_files_in_dir(dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(it) => it,
+        Err(_) => return Vec::new(),
+    };
+    let mut acc = Vec::new();
+    for entry in entries {
+        let path = entry.unwrap().path();
+        let extension = path.extension().unwrap_or_default();
+        if extension == "rs" || extension == "txt" {
+            acc.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    acc.sort();
+    acc
+}
+
+#[test]
+fn assert_dirs_eq_compares_files() {
+    let root = env::temp_dir().join(format!("test_utils_dirs_eq_{}", std::process::id()));
+    let write_dir = |name: &str, files: &[(&str, &str)]| {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, text) in files {
+            fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    };
+    let expected = write_dir("expected", &[("lib.rs", "fn foo() {}\n"), ("out.txt", "ok\n")]);
+    let same = write_dir("same", &[("lib.rs", "fn foo() {}\n"), ("out.txt", "ok\n")]);
+    let changed = write_dir("changed", &[("lib.rs", "fn bar() {}\n"), ("out.txt", "ok\n")]);
+    let renamed = write_dir("renamed", &[("main.rs", "fn foo() {}\n"), ("out.txt", "ok\n")]);
+
+    if env::var("UPDATE_EXPECTATIONS").is_err() {
+        assert_dirs_eq(&expected, &same);
+        assert_panics_with("text differs", || assert_dirs_eq(&expected, &changed));
+        assert_panics_with("[..]missing from [..][\"lib.rs\"][..]", || {
+            assert_dirs_eq(&expected, &renamed)
+        });
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
 /// Returns the path to the root directory of `rust-analyzer` project.
 pub fn project_dir() -> PathBuf {
     let dir = env!("CARGO_MANIFEST_DIR");
@@ -339,6 +1240,104 @@ pub fn skip_slow_tests() -> bool {
     should_skip
 }
 
+/// Runs `f` with the environment variable `key` set to `value`, restoring its
+/// previous value (or removing it) afterwards, even if `f` panics.
+///
+/// Calls are serialized by a global lock, so tests running in parallel don't
+/// observe each other's variables. For the same reason calls must not nest.
+pub fn with_env_var<T>(key: &str, value: &str, f: impl FnOnce() -> T) -> T {
+    let _guard = EnvVarGuard::set(key, value);
+    f()
+}
+
+static ENV_LOCKED: AtomicBool = AtomicBool::new(false);
+
+struct EnvVarGuard {
+    key: String,
+    prev: Option<OsString>,
+}
+
+impl EnvVarGuard {
+    fn set(key: &str, value: &str) -> EnvVarGuard {
+        // A spin lock, as a `static Mutex` would need a lazy initialization
+        // dependency.
+        while ENV_LOCKED
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            thread::yield_now();
+        }
+        let prev = env::var_os(key);
+        env::set_var(key, value);
+        EnvVarGuard { key: key.to_string(), prev }
+    }
+}
+
+impl Drop for EnvVarGuard {
+    fn drop(&mut self) {
+        match &self.prev {
+            Some(prev) => env::set_var(&self.key, prev),
+            None => env::remove_var(&self.key),
+        }
+        ENV_LOCKED.store(false, Ordering::Release);
+    }
+}
+
+#[test]
+fn with_env_var_restores_previous_value() {
+    let key = "TEST_UTILS_WITH_ENV_VAR";
+    assert_eq!(env::var_os(key), None);
+    let value = with_env_var(key, "inner", || env::var(key).unwrap());
+    assert_eq!(value, "inner");
+    assert_eq!(env::var_os(key), None);
+
+    env::set_var(key, "outer");
+    with_env_var(key, "inner", || assert_eq!(env::var(key).unwrap(), "inner"));
+    assert_eq!(env::var(key).unwrap(), "outer");
+    env::remove_var(key);
+}
+
+/// Runs `f` and panics if it takes longer than `max`, as a cheap tripwire for
+/// performance regressions. On CI, where shared runners make timings noisy,
+/// the overrun is only logged.
+pub fn time_bounded<T>(max: Duration, f: impl FnOnce() -> T) -> T {
+    check_time_bound(max, env::var_os("CI").is_some(), f)
+}
+
+fn check_time_bound<T>(max: Duration, on_ci: bool, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let res = f();
+    let elapsed = start.elapsed();
+    if elapsed > max {
+        if on_ci {
+            eprintln!("time bound exceeded: took {:?}, expected at most {:?}", elapsed, max);
+        } else {
+            panic!("time bound exceeded: took {:?}, expected at most {:?}", elapsed, max);
+        }
+    }
+    res
+}
+
+#[test]
+fn time_bounded_passes_fast_closures() {
+    assert_eq!(check_time_bound(Duration::from_secs(10), false, || 92), 92);
+}
+
+#[test]
+#[should_panic(expected = "time bound exceeded")]
+fn time_bounded_panics_on_slow_closures() {
+    check_time_bound(Duration::from_millis(1), false, || {
+        std::thread::sleep(Duration::from_millis(20))
+    });
+}
+
+#[test]
+fn time_bounded_only_logs_on_ci() {
+    check_time_bound(Duration::from_millis(1), true, || {
+        std::thread::sleep(Duration::from_millis(20))
+    });
+}
+
 /// Asserts that `expected` and `actual` strings are equal. If they differ only
 /// in trailing or leading whitespace the test won't fail and
 /// the contents of `actual` will be written to the file located at `path`.
@@ -351,13 +1350,154 @@ fn assert_equal_text(expected: &str, actual: &str, path: &Path) {
     if expected.trim() == actual.trim() {
         println!("whitespace difference, rewriting");
         println!("file: {}\n", pretty_path.display());
-        fs::write(path, actual).unwrap();
+        rewrite_expectation(path, actual);
         return;
     }
     if env::var("UPDATE_EXPECTATIONS").is_ok() {
         println!("rewriting {}", pretty_path.display());
-        fs::write(path, actual).unwrap();
+        rewrite_expectation(path, actual);
         return;
     }
     assert_eq_text!(expected, actual, "file: {}", pretty_path.display());
 }
+
+static UPDATED_EXPECTATIONS: AtomicUsize = AtomicUsize::new(0);
+
+fn rewrite_expectation(path: &Path, actual: &str) {
+    fs::write(path, actual).unwrap();
+    UPDATED_EXPECTATIONS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Returns the number of expectation files rewritten so far by this process.
+pub fn updated_expectations_count() -> usize {
+    UPDATED_EXPECTATIONS.load(Ordering::SeqCst)
+}
+
+/// Prints how many expectation files were rewritten, if any. Meant to be
+/// called once all the tests have run.
+pub fn print_update_summary() {
+    let count = updated_expectations_count();
+    if count != 0 {
+        println!("{} expectation files updated", count);
+    }
+}
+
+#[test]
+fn rewrites_are_counted() {
+    let dir = env::temp_dir().join(format!("test_utils_rewrites_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let before = updated_expectations_count();
+    for name in &["a.txt", "b.txt"] {
+        let path = dir.join(name);
+        fs::write(&path, "old").unwrap();
+        assert_equal_text("old", "old\n", &path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\n");
+    }
+    assert_eq!(updated_expectations_count() - before, 2);
+}
+
+/// Asserts that `actual` is equal to the `expected` raw string literal.
+///
+/// With `UPDATE_EXPECTATIONS` set, a mismatching literal is instead replaced
+/// with `actual` right in the source file of the caller. If this changes the
+/// number of lines, later assertions in the same file may need another run.
+#[macro_export]
+macro_rules! assert_inline {
+    ($actual:expr, $expected:literal) => {
+        $crate::assert_inline_impl(
+            &$actual,
+            $expected,
+            stringify!($expected),
+            file!(),
+            line!(),
+            column!(),
+        )
+    };
+}
+
+#[doc(hidden)]
+pub fn assert_inline_impl(
+    actual: &str,
+    expected: &str,
+    expected_literal: &str,
+    file: &str,
+    line: u32,
+    column: u32,
+) {
+    assert!(
+        expected_literal.starts_with('r'),
+        "assert_inline! expects a raw string literal, got `{}` at {}:{}",
+        expected_literal,
+        file,
+        line
+    );
+    if actual == expected {
+        return;
+    }
+    if env::var("UPDATE_EXPECTATIONS").is_ok() {
+        let path = project_dir().join(file);
+        let source = read_text(&path);
+        let offset = line_column_to_offset(&source, line, column);
+        println!("rewriting {}:{}", file, line);
+        let new_source = replace_literal_after(&source, offset, expected_literal, actual);
+        rewrite_expectation(&path, &new_source);
+        return;
+    }
+    assert_eq_text!(expected, actual, "{}:{}", file, line);
+}
+
+/// Converts the 1-based `line` and `column` (in chars) to a byte offset.
+fn line_column_to_offset(text: &str, line: u32, column: u32) -> usize {
+    let line_start = text.split('\n').take(line as usize - 1).map(|it| it.len() + 1).sum::<usize>();
+    let column_len =
+        text[line_start..].chars().take(column as usize - 1).map(char::len_utf8).sum::<usize>();
+    line_start + column_len
+}
+
+/// Replaces the first occurrence of the raw string `literal` at or after
+/// `offset` in `source` with a raw string containing `value`, using as many
+/// `#` as needed. Matching the literal verbatim keeps an `r"` inside the
+/// asserted expression, or any later raw string, from being picked instead.
+fn replace_literal_after(source: &str, offset: usize, literal: &str, value: &str) -> String {
+    let start = offset
+        + source[offset..].find(literal).expect("can't find the expected literal in the source");
+    let end = start + literal.len();
+
+    let mut new_hashes = 1;
+    while value.contains(&format!("\"{}", "#".repeat(new_hashes))) {
+        new_hashes += 1;
+    }
+    let hashes = "#".repeat(new_hashes);
+    format!("{}r{}\"{}\"{}{}", &source[..start], hashes, value, hashes, &source[end..])
+}
+
+#[test]
+fn assert_inline_passes_on_equal_text() {
+    assert_inline!(format!("{}, {}", "hello", "world"), r#"hello, world"#);
+}
+
+#[test]
+fn assert_inline_rewrites_literal_in_source() {
+    let source = "fn t() {\n    assert_inline!(foo(), r#\"old\"#);\n}\n";
+    let offset = line_column_to_offset(source, 2, 5);
+    assert_eq!(&source[offset..offset + 13], "assert_inline");
+    assert_eq!(
+        replace_literal_after(source, offset, "r#\"old\"#", "new \"# text"),
+        "fn t() {\n    assert_inline!(foo(), r##\"new \"# text\"##);\n}\n"
+    );
+}
+
+#[test]
+fn assert_inline_skips_raw_strings_in_actual() {
+    let source = "assert_inline!(format!(r\"{}r\", 1), r\"1r\");\nlet later = r\"keep\";\n";
+    assert_eq!(
+        replace_literal_after(source, 0, "r\"1r\"", "2r"),
+        "assert_inline!(format!(r\"{}r\", 1), r#\"2r\"#);\nlet later = r\"keep\";\n"
+    );
+}
+
+#[test]
+#[should_panic(expected = "expects a raw string literal")]
+fn assert_inline_rejects_plain_literals() {
+    assert_inline!(String::from("x"), "x");
+}
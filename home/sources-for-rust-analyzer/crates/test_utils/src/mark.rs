@@ -55,6 +55,32 @@ macro_rules! _check {
 }
 pub use _check as check;
 
+/// Expands to an array of `(name, counter)` pairs for the given marks, to be
+/// passed to `assert_all_checked`. Each mark must be defined by a `check!`.
+#[macro_export]
+macro_rules! _marks {
+    ($($ident:ident),* $(,)?) => {{
+        extern "C" {
+            $(
+                #[no_mangle]
+                static $ident: std::sync::atomic::AtomicUsize;
+            )*
+        }
+        unsafe { [$((stringify!($ident), &$ident)),*] }
+    }};
+}
+pub use _marks as marks;
+
+/// Fails if any of the `marks` (see `marks!`) was never hit during the test run.
+/// Meant to be called after the rest of the suite has run, to detect marks whose
+/// `hit!` site is no longer reachable.
+pub fn assert_all_checked(marks: &[(&str, &AtomicUsize)]) {
+    let unhit =
+        marks.iter().filter(|(_, mark)| mark.load(Ordering::SeqCst) == 0).map(|(name, _)| *name);
+    let unhit = unhit.collect::<Vec<_>>();
+    assert!(unhit.is_empty(), "marks were never hit: {}", unhit.join(", "));
+}
+
 pub struct MarkChecker {
     mark: &'static AtomicUsize,
     value_on_entry: usize,
@@ -76,3 +102,17 @@ impl Drop for MarkChecker {
         assert!(value_on_exit > self.value_on_entry, "mark was not hit")
     }
 }
+
+#[test]
+#[ignore]
+#[should_panic(expected = "marks were never hit: test_utils_unhit_mark")]
+#[allow(non_upper_case_globals)]
+fn assert_all_checked_detects_unhit_marks() {
+    #[no_mangle]
+    static test_utils_hit_mark: AtomicUsize = AtomicUsize::new(0);
+    #[no_mangle]
+    static test_utils_unhit_mark: AtomicUsize = AtomicUsize::new(0);
+
+    hit!(test_utils_hit_mark);
+    assert_all_checked(&marks![test_utils_hit_mark, test_utils_unhit_mark]);
+}
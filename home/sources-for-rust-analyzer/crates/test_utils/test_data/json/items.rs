@@ -0,0 +1,7 @@
+struct Answer;
+
+fn answer() -> u32 {
+    92
+}
+
+fn main() {}
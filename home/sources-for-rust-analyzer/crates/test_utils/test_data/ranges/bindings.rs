@@ -0,0 +1,4 @@
+fn main() {
+    let x = 92;
+    let y = x;
+}
@@ -0,0 +1,6 @@
+fn answer() -> u32 {
+    let answer = 92;
+    answer
+}
+
+fn main() {}
@@ -1761,6 +1761,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
         /// ❰ let bar: u64; ❱
         /// ❰ let baz = 42; ❱
         /// ❰ let bruh: bool = true; ❱
+        /// ❰ let Some(qux) = opt else { return }; ❱
         /// ```
         ///
         /// [Reference](https://doc.rust-lang.org/reference/statements.html#let-statements)
@@ -1769,6 +1770,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
             Pat,
             T![=],
             initializer: Expr,
+            T![else],
             T![;],
         }
 